@@ -0,0 +1,575 @@
+//! Deck import and export dialogs.
+//! JSON files are imported directly; CSV/TSV files open a dialog where the
+//! format and column mapping are chosen with a preview of the first rows, and
+//! Anki packages open a dialog to name the deck and keep review progress.
+//! Decks whose name already exists are merged after choosing how conflicting
+//! cards are resolved. Full backups are restored only after confirmation, since they replace
+//! everything in the database.
+
+use super::MyApp;
+use crate::database::db;
+use crate::export::anki::{AnkiPackage, import_apkg_package, read_apkg};
+use crate::export::backup::{backup_to_path, read_backup, restore_backup};
+use crate::export::csv::{
+    CsvOptions, CsvPreview, export_csv_to_path, import_csv, is_delimited_text, preview_csv,
+};
+use crate::export::json::{export_json_to_path, import_json};
+use crate::export::merge::{Conflict, ConflictPolicy, Resolution, find_conflicts, merge_deck};
+use crate::models::Deck;
+use chrono::{DateTime, Local};
+use eframe::egui;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Number of rows shown in the CSV import preview
+const PREVIEW_ROWS: usize = 10;
+
+/// Delimiters offered in the CSV import dialog
+const DELIMITERS: [(u8, &str); 4] = [
+    (b',', "Comma"),
+    (b';', "Semicolon"),
+    (b'\t', "Tab"),
+    (b'|', "Pipe"),
+];
+
+/// CSV/TSV file waiting for the user to confirm its format
+pub(super) struct CsvImport {
+    path: PathBuf,
+    deck_name: String,
+    options: CsvOptions,
+    /// Preview of the file with the current options, or why it cannot be read
+    preview: Result<CsvPreview, String>,
+}
+
+impl CsvImport {
+    fn new(path: PathBuf) -> Self {
+        let deck_name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let options = CsvOptions::for_path(&path);
+        let mut import = Self {
+            path,
+            deck_name,
+            options,
+            preview: Ok(CsvPreview::default()),
+        };
+        import.refresh_preview();
+        if let Ok(preview) = &import.preview {
+            import.options.columns.tags = preview.tags_column();
+        }
+        import
+    }
+
+    /// Re-reads the preview after the options changed
+    fn refresh_preview(&mut self) {
+        self.preview = File::open(&self.path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| {
+                preview_csv(file, &self.options, PREVIEW_ROWS).map_err(|e| e.to_string())
+            });
+    }
+}
+
+/// Anki package waiting for the user to confirm the import
+pub(super) struct AnkiImport {
+    package: AnkiPackage,
+    deck_name: String,
+    keep_scheduling: bool,
+}
+
+/// Imported deck waiting to be merged into the existing deck of the same name
+pub(super) struct MergeImport {
+    deck: Deck,
+    policy: ConflictPolicy,
+    conflicts: Vec<Conflict>,
+    /// Chosen resolution for each conflict, used with `ConflictPolicy::Ask`
+    resolutions: Vec<Resolution>,
+}
+
+/// Returns true for Anki packages
+fn is_anki_package(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("apkg"))
+}
+
+/// Name of a column in the CSV import dialog
+fn column_name(preview: &CsvPreview, index: usize) -> String {
+    match preview.headers.as_ref().and_then(|h| h.get(index)) {
+        Some(header) if !header.is_empty() => format!("{}: {}", index + 1, header),
+        _ => format!("Column {}", index + 1),
+    }
+}
+
+impl MyApp {
+    /// Handles deck export to a JSON, CSV or TSV file
+    pub(super) fn handle_export(&mut self, deck_index: usize) {
+        if let Some(deck) = self.all_decks.decks.get(deck_index) {
+            // Open file save dialog
+            if let Some(path) = rfd::FileDialog::new()
+                .set_file_name(format!("{}.json", deck.name))
+                .add_filter("JSON files", &["json"])
+                .add_filter("CSV files", &["csv"])
+                .add_filter("TSV files", &["tsv"])
+                .save_file()
+            {
+                let result = if is_delimited_text(&path) {
+                    export_csv_to_path(deck, &path, &CsvOptions::for_path(&path))
+                } else {
+                    export_json_to_path(deck, &path.to_string_lossy(), &self.media)
+                };
+                let message = match result {
+                    Ok(_) => format!("Deck '{}' exported successfully!", deck.name),
+                    Err(e) => format!("Export failed: {}", e),
+                };
+                self.show_message(message);
+            }
+        }
+        self.show_export_dialog = false;
+    }
+
+    /// Handles deck import from a JSON, CSV, TSV or Anki package file
+    pub(super) fn handle_import(&mut self) {
+        // Open file selection dialog
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Deck files", &["json", "csv", "tsv", "tab", "txt", "apkg"])
+            .pick_file()
+        {
+            if is_delimited_text(&path) {
+                self.csv_import = Some(CsvImport::new(path));
+            } else if is_anki_package(&path) {
+                match read_apkg(&path) {
+                    Ok(package) => {
+                        self.anki_import = Some(AnkiImport {
+                            deck_name: package.deck_name.clone(),
+                            package,
+                            keep_scheduling: true,
+                        });
+                    }
+                    Err(e) => self.show_message(format!("Import failed: {}", e)),
+                }
+            } else {
+                self.import_json_file(&path);
+            }
+        }
+    }
+
+    fn import_json_file(&mut self, path: &Path) {
+        match import_json(&path.to_string_lossy(), &self.media) {
+            Ok(deck) => self.import_deck(deck),
+            Err(e) => {
+                self.show_message(format!(
+                    "Import failed: {}\n\nPlease check if the file has correct structure:\n{{\n  \"name\": \"Deck Name\",\n  \"flashcards\": [...]\n}}",
+                    e
+                ));
+            }
+        }
+    }
+
+    /// Saves an imported deck and reports the result
+    ///
+    /// If a deck with the same name exists, the merge dialog is opened instead.
+    fn import_deck(&mut self, deck: Deck) {
+        if self.all_decks.decks.iter().any(|d| d.name == deck.name) {
+            if let Some(conflicts) = self.with_db(|conn| find_conflicts(&deck, &deck.name, conn)) {
+                let resolutions = vec![Resolution::KeepExisting; conflicts.len()];
+                self.merge_import = Some(MergeImport {
+                    deck,
+                    policy: ConflictPolicy::default(),
+                    conflicts,
+                    resolutions,
+                });
+            }
+            return;
+        }
+
+        if self.with_db(|conn| db::import_deck(&deck, conn)).is_some() {
+            self.reload_decks(Some(&deck.name));
+            self.show_message(format!(
+                "Deck '{}' imported successfully with {} cards!",
+                deck.name,
+                deck.flashcards.len()
+            ));
+        }
+    }
+
+    /// Renders the CSV/TSV import dialog with format options and a preview
+    pub(super) fn render_csv_import_dialog(&mut self, ctx: &egui::Context) {
+        let Some(import) = &mut self.csv_import else {
+            return;
+        };
+        let mut options = import.options.clone();
+        let mut confirm = false;
+        let mut cancel = false;
+
+        egui::Window::new("Import CSV")
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(import.path.display().to_string());
+                ui.horizontal(|ui| {
+                    ui.label("Deck name:");
+                    ui.text_edit_singleline(&mut import.deck_name);
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Delimiter:");
+                    let selected = DELIMITERS
+                        .iter()
+                        .find(|(d, _)| *d == options.delimiter)
+                        .map_or("Other", |(_, label)| label);
+                    egui::ComboBox::from_id_salt("csv_delimiter")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            for (delimiter, label) in DELIMITERS {
+                                ui.selectable_value(&mut options.delimiter, delimiter, label);
+                            }
+                        });
+                    ui.checkbox(&mut options.quoting, "Quoted fields");
+                    ui.checkbox(&mut options.has_header, "Header row");
+                });
+
+                match &import.preview {
+                    Ok(preview) => {
+                        let columns = preview.column_count.max(2);
+                        ui.horizontal(|ui| {
+                            ui.label("Term:");
+                            egui::ComboBox::from_id_salt("csv_term_column")
+                                .selected_text(column_name(preview, options.columns.term))
+                                .show_ui(ui, |ui| {
+                                    for i in 0..columns {
+                                        ui.selectable_value(
+                                            &mut options.columns.term,
+                                            i,
+                                            column_name(preview, i),
+                                        );
+                                    }
+                                });
+                            ui.label("Definition:");
+                            egui::ComboBox::from_id_salt("csv_definition_column")
+                                .selected_text(column_name(preview, options.columns.definition))
+                                .show_ui(ui, |ui| {
+                                    for i in 0..columns {
+                                        ui.selectable_value(
+                                            &mut options.columns.definition,
+                                            i,
+                                            column_name(preview, i),
+                                        );
+                                    }
+                                });
+                            ui.label("Tags:");
+                            egui::ComboBox::from_id_salt("csv_tags_column")
+                                .selected_text(
+                                    options
+                                        .columns
+                                        .tags
+                                        .map_or("None".to_string(), |i| column_name(preview, i)),
+                                )
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut options.columns.tags, None, "None");
+                                    for i in 0..columns {
+                                        ui.selectable_value(
+                                            &mut options.columns.tags,
+                                            Some(i),
+                                            column_name(preview, i),
+                                        );
+                                    }
+                                });
+                        });
+
+                        ui.separator();
+                        egui::Grid::new("csv_preview").striped(true).show(ui, |ui| {
+                            ui.strong("Term");
+                            ui.strong("Definition");
+                            ui.strong("Tags");
+                            ui.end_row();
+                            for row in &preview.rows {
+                                let field = |i: usize| row.get(i).map_or("", String::as_str);
+                                ui.label(field(options.columns.term));
+                                ui.label(field(options.columns.definition));
+                                ui.label(options.columns.tags.map_or("", field));
+                                ui.end_row();
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        ui.colored_label(egui::Color32::RED, format!("Cannot read file: {}", e));
+                    }
+                }
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Import").clicked() {
+                        confirm = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if options != import.options {
+            import.options = options;
+            import.refresh_preview();
+        }
+
+        if confirm && let Some(import) = self.csv_import.take() {
+            let name = import.deck_name.trim();
+            match import_csv(&import.path, name, &import.options) {
+                Ok(deck) => self.import_deck(deck),
+                Err(e) => {
+                    self.show_message(format!("Import failed: {}", e));
+                    // Keep the dialog open so the options can be corrected
+                    self.csv_import = Some(import);
+                }
+            }
+        }
+        if cancel {
+            self.csv_import = None;
+        }
+    }
+
+    /// Renders the Anki package import dialog
+    pub(super) fn render_anki_import_dialog(&mut self, ctx: &egui::Context) {
+        let Some(import) = &mut self.anki_import else {
+            return;
+        };
+        let mut confirm = false;
+        let mut cancel = false;
+
+        egui::Window::new("Import Anki Package")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let reviewed = import
+                    .package
+                    .cards
+                    .iter()
+                    .filter(|card| card.schedule.is_some())
+                    .count();
+                ui.label(format!(
+                    "{} notes found, {} of them reviewed in Anki.",
+                    import.package.cards.len(),
+                    reviewed
+                ));
+                for omission in import.package.omissions() {
+                    ui.label(omission);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Deck name:");
+                    ui.text_edit_singleline(&mut import.deck_name);
+                });
+                ui.checkbox(
+                    &mut import.keep_scheduling,
+                    "Keep review progress from Anki",
+                );
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Import").clicked() {
+                        confirm = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if confirm && let Some(import) = self.anki_import.take() {
+            let name = import.deck_name.trim().to_string();
+            let imported = self.with_db(|conn| {
+                import_apkg_package(&import.package, &name, import.keep_scheduling, conn)
+            });
+            if imported.is_some() {
+                self.reload_decks(Some(&name));
+                self.show_message(format!(
+                    "Deck '{}' imported successfully with {} cards!",
+                    name,
+                    import.package.cards.len()
+                ));
+            } else {
+                // Keep the dialog open so another name can be chosen
+                self.anki_import = Some(import);
+            }
+        }
+        if cancel {
+            self.anki_import = None;
+        }
+    }
+
+    /// Writes a full backup of the database to a file chosen by the user
+    pub(super) fn handle_backup(&mut self) {
+        let file_name = format!("flashcards-backup-{}.json", Local::now().format("%Y-%m-%d"));
+        if let Some(path) = rfd::FileDialog::new()
+            .set_file_name(file_name)
+            .add_filter("JSON files", &["json"])
+            .save_file()
+            && let Some(backup) = self.with_db(|conn| backup_to_path(conn, &path))
+        {
+            self.show_message(format!(
+                "Backup saved with {} decks, {} cards and {} reviews.",
+                backup.decks.len(),
+                backup.flashcards.len(),
+                backup.review_log.len()
+            ));
+        }
+    }
+
+    /// Reads a backup file and asks for confirmation before restoring it
+    pub(super) fn handle_restore(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON files", &["json"])
+            .pick_file()
+        {
+            match read_backup(&path) {
+                Ok(backup) => self.pending_restore = Some(backup),
+                Err(e) => self.show_message(format!("Cannot read backup: {}", e)),
+            }
+        }
+    }
+
+    /// Renders the confirmation dialog for restoring a backup
+    pub(super) fn render_restore_dialog(&mut self, ctx: &egui::Context) {
+        let Some(backup) = &self.pending_restore else {
+            return;
+        };
+        let mut confirm = false;
+        let mut cancel = false;
+
+        egui::Window::new("Restore Backup")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let created: DateTime<Local> = DateTime::from_timestamp(backup.created_at, 0)
+                    .unwrap_or_default()
+                    .into();
+                ui.label(format!(
+                    "Backup from {} with {} decks, {} cards and {} reviews.",
+                    created.format("%Y-%m-%d %H:%M"),
+                    backup.decks.len(),
+                    backup.flashcards.len(),
+                    backup.review_log.len()
+                ));
+                ui.label("Restoring replaces all current decks, cards, progress and settings.");
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Restore").clicked() {
+                        confirm = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if confirm && let Some(backup) = self.pending_restore.take() {
+            let restored = self.with_db(|conn| {
+                restore_backup(&backup, conn)?;
+                Ok((db::get_clock_mode(conn)?, db::get_rollover_hour(conn)?))
+            });
+            if let Some((clock_mode, rollover_hour)) = restored {
+                self.clock_mode = clock_mode;
+                self.rollover_hour = rollover_hour;
+                self.reload_decks(None);
+                self.show_message("Backup restored.");
+            }
+        }
+        if cancel {
+            self.pending_restore = None;
+        }
+    }
+
+    /// Renders the dialog for merging an import into an existing deck
+    pub(super) fn render_merge_dialog(&mut self, ctx: &egui::Context) {
+        let Some(merge) = &mut self.merge_import else {
+            return;
+        };
+        let mut confirm = false;
+        let mut cancel = false;
+
+        egui::Window::new(format!("Merge into '{}'", merge.deck.name))
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Deck '{}' already exists. {} of the {} imported cards have a different definition.",
+                    merge.deck.name,
+                    merge.conflicts.len(),
+                    merge.deck.flashcards.len()
+                ));
+
+                ui.horizontal(|ui| {
+                    ui.label("Conflicting cards:");
+                    egui::ComboBox::from_id_salt("merge_policy")
+                        .selected_text(merge.policy.label())
+                        .show_ui(ui, |ui| {
+                            for policy in ConflictPolicy::ALL {
+                                ui.selectable_value(&mut merge.policy, policy, policy.label());
+                            }
+                        });
+                });
+
+                if merge.policy == ConflictPolicy::Ask && !merge.conflicts.is_empty() {
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .id_salt("merge_conflicts")
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            for (conflict, resolution) in
+                                merge.conflicts.iter().zip(merge.resolutions.iter_mut())
+                            {
+                                ui.group(|ui| {
+                                    ui.strong(&conflict.term);
+                                    ui.label(format!("Existing: {}", conflict.existing_definition));
+                                    ui.label(format!("Imported: {}", conflict.imported_definition));
+                                    ui.horizontal(|ui| {
+                                        ui.radio_value(
+                                            resolution,
+                                            Resolution::KeepExisting,
+                                            "Keep existing",
+                                        );
+                                        ui.radio_value(resolution, Resolution::Overwrite, "Overwrite");
+                                        ui.radio_value(resolution, Resolution::KeepBoth, "Keep both");
+                                    });
+                                });
+                            }
+                        });
+                }
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Merge").clicked() {
+                        confirm = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if confirm && let Some(merge) = self.merge_import.take() {
+            let report = self.with_db(|conn| {
+                merge_deck(
+                    &merge.deck,
+                    &merge.deck.name,
+                    merge.policy,
+                    |conflict| {
+                        merge
+                            .conflicts
+                            .iter()
+                            .position(|c| c.term == conflict.term)
+                            .map_or(Resolution::KeepExisting, |i| merge.resolutions[i])
+                    },
+                    conn,
+                )
+            });
+            if let Some(report) = report {
+                self.reload_decks(Some(&merge.deck.name));
+                self.show_message(format!("Deck '{}' merged: {}.", merge.deck.name, report));
+            }
+        }
+        if cancel {
+            self.merge_import = None;
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Rendering of Markdown card faces and the editor with a live preview.
+//! See `models::markdown` for the supported subset.
+
+use crate::models::markdown::{Block, Span, parse};
+use eframe::egui;
+
+/// Indentation of list items
+const LIST_INDENT: f32 = 12.0;
+
+/// Lays out a line of spans; bold text uses the strong text color since
+/// the default fonts have no bold face
+fn spans_layout(ui: &egui::Ui, spans: &[Span], prefix: &str) -> egui::text::LayoutJob {
+    let visuals = ui.visuals();
+    let body = egui::TextStyle::Body.resolve(ui.style());
+    let mut job = egui::text::LayoutJob::default();
+    job.append(
+        prefix,
+        0.0,
+        egui::TextFormat::simple(body.clone(), visuals.text_color()),
+    );
+
+    for span in spans {
+        let mut format = egui::TextFormat::simple(body.clone(), visuals.text_color());
+        if span.bold {
+            format.color = visuals.strong_text_color();
+        }
+        format.italics = span.italic;
+        if span.code {
+            format.font_id = egui::TextStyle::Monospace.resolve(ui.style());
+            format.background = visuals.code_bg_color;
+        }
+        job.append(&span.text, 0.0, format);
+    }
+    job
+}
+
+/// Renders Markdown card text
+pub(super) fn render_markdown(ui: &mut egui::Ui, text: &str) {
+    for block in parse(text) {
+        match block {
+            Block::Line(spans) => {
+                ui.label(spans_layout(ui, &spans, ""));
+            }
+            Block::Bullet(spans) => {
+                ui.horizontal_wrapped(|ui| {
+                    ui.add_space(LIST_INDENT);
+                    ui.label(spans_layout(ui, &spans, "• "));
+                });
+            }
+            Block::Numbered(number, spans) => {
+                ui.horizontal_wrapped(|ui| {
+                    ui.add_space(LIST_INDENT);
+                    ui.label(spans_layout(ui, &spans, &format!("{}. ", number)));
+                });
+            }
+            Block::Code(code) => {
+                egui::Frame::none()
+                    .fill(ui.visuals().code_bg_color)
+                    .inner_margin(4.0)
+                    .rounding(2.0)
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new(code).monospace());
+                    });
+            }
+        }
+    }
+}
+
+/// Multi-line text field with the rendered text next to it
+pub(super) fn markdown_editor(ui: &mut egui::Ui, label: &str, text: &mut String) {
+    ui.label(label);
+    ui.horizontal_top(|ui| {
+        ui.add(
+            egui::TextEdit::multiline(text)
+                .desired_rows(3)
+                .desired_width(250.0),
+        );
+        ui.group(|ui| {
+            ui.set_min_width(200.0);
+            ui.vertical(|ui| render_markdown(ui, text));
+        });
+    });
+}
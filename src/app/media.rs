@@ -0,0 +1,210 @@
+//! Images and audio clips on card faces: showing and playing them from the
+//! media store and choosing them in the add and edit forms. Chosen files are
+//! copied into the store right away; a file no card refers to anymore is
+//! simply never shown.
+
+use super::MyApp;
+use crate::database::db;
+use crate::database::media::{MediaKind, MediaStore};
+use crate::error::Result;
+use crate::models::Flashcard;
+use eframe::egui;
+use rusqlite::Connection;
+
+/// Height of the images shown while learning
+pub(super) const CARD_IMAGE_HEIGHT: f32 = 200.0;
+/// Height of the previews in forms and the card list
+pub(super) const THUMBNAIL_HEIGHT: f32 = 48.0;
+
+/// Media file of one side of a card
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum MediaSlot {
+    TermImage,
+    TermAudio,
+    DefinitionImage,
+    DefinitionAudio,
+}
+
+impl MediaSlot {
+    /// Slots shown below the term
+    pub(super) const TERM: [MediaSlot; 2] = [MediaSlot::TermImage, MediaSlot::TermAudio];
+    /// Slots shown below the definition
+    pub(super) const DEFINITION: [MediaSlot; 2] =
+        [MediaSlot::DefinitionImage, MediaSlot::DefinitionAudio];
+
+    fn kind(&self) -> MediaKind {
+        match self {
+            MediaSlot::TermImage | MediaSlot::DefinitionImage => MediaKind::Image,
+            MediaSlot::TermAudio | MediaSlot::DefinitionAudio => MediaKind::Audio,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            MediaSlot::TermImage => "Term image:",
+            MediaSlot::TermAudio => "Term audio:",
+            MediaSlot::DefinitionImage => "Definition image:",
+            MediaSlot::DefinitionAudio => "Definition audio:",
+        }
+    }
+}
+
+/// Request made in a media field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum MediaAction {
+    Choose,
+    Remove,
+    Play,
+}
+
+/// Media files of a card being added or edited
+#[derive(Clone, Debug, Default)]
+pub(super) struct CardMedia {
+    term_image: Option<String>,
+    term_audio: Option<String>,
+    definition_image: Option<String>,
+    definition_audio: Option<String>,
+}
+
+impl CardMedia {
+    pub(super) fn of(flashcard: &Flashcard) -> Self {
+        Self {
+            term_image: flashcard.term_image.clone(),
+            term_audio: flashcard.term_audio.clone(),
+            definition_image: flashcard.definition_image.clone(),
+            definition_audio: flashcard.definition_audio.clone(),
+        }
+    }
+
+    fn get(&self, slot: MediaSlot) -> Option<&str> {
+        match slot {
+            MediaSlot::TermImage => self.term_image.as_deref(),
+            MediaSlot::TermAudio => self.term_audio.as_deref(),
+            MediaSlot::DefinitionImage => self.definition_image.as_deref(),
+            MediaSlot::DefinitionAudio => self.definition_audio.as_deref(),
+        }
+    }
+
+    fn slot_mut(&mut self, slot: MediaSlot) -> &mut Option<String> {
+        match slot {
+            MediaSlot::TermImage => &mut self.term_image,
+            MediaSlot::TermAudio => &mut self.term_audio,
+            MediaSlot::DefinitionImage => &mut self.definition_image,
+            MediaSlot::DefinitionAudio => &mut self.definition_audio,
+        }
+    }
+
+    /// Stores the media of a flashcard
+    pub(super) fn save(&self, flashcard_id: i64, conn: &Connection) -> Result<()> {
+        db::set_flashcard_images(
+            flashcard_id,
+            self.term_image.as_deref(),
+            self.definition_image.as_deref(),
+            conn,
+        )?;
+        db::set_flashcard_audio(
+            flashcard_id,
+            self.term_audio.as_deref(),
+            self.definition_audio.as_deref(),
+            conn,
+        )
+    }
+}
+
+/// Shows a stored image scaled down to `max_height`
+pub(super) fn card_image(ui: &mut egui::Ui, media: &MediaStore, name: &str, max_height: f32) {
+    match media.path(name) {
+        Ok(path) if path.exists() => {
+            ui.add(
+                egui::Image::new(format!("file://{}", path.display()))
+                    .max_height(max_height)
+                    .fit_to_original_size(1.0),
+            );
+        }
+        _ => {
+            ui.label(format!("(missing image {})", name));
+        }
+    }
+}
+
+/// Rows with the media of one side of a card and buttons to change them
+pub(super) fn media_fields(
+    ui: &mut egui::Ui,
+    card: &CardMedia,
+    slots: [MediaSlot; 2],
+    media: &MediaStore,
+) -> Option<(MediaSlot, MediaAction)> {
+    let mut action = None;
+    for slot in slots {
+        let name = card.get(slot);
+        ui.horizontal(|ui| {
+            ui.label(slot.label());
+            if let Some(name) = name {
+                match slot.kind() {
+                    MediaKind::Image => card_image(ui, media, name, THUMBNAIL_HEIGHT),
+                    MediaKind::Audio => {
+                        if ui.button("▶ Play").clicked() {
+                            action = Some((slot, MediaAction::Play));
+                        }
+                    }
+                }
+            }
+            if ui
+                .button(format!("Choose {}…", slot.kind().label()))
+                .clicked()
+            {
+                action = Some((slot, MediaAction::Choose));
+            }
+            if name.is_some() && ui.button("Remove").clicked() {
+                action = Some((slot, MediaAction::Remove));
+            }
+        });
+    }
+    action
+}
+
+impl MyApp {
+    /// Carries out a request made in a media field of a form
+    ///
+    /// Choosing opens a file dialog and copies the picked file into the media
+    /// store; the current file is kept if nothing is picked or copying fails.
+    pub(super) fn apply_media_action(
+        &mut self,
+        card: &mut CardMedia,
+        slot: MediaSlot,
+        action: MediaAction,
+    ) {
+        let kind = slot.kind();
+        match action {
+            MediaAction::Remove => *card.slot_mut(slot) = None,
+            MediaAction::Play => {
+                if let Some(name) = card.get(slot).map(str::to_string) {
+                    self.play_audio(&name);
+                }
+            }
+            MediaAction::Choose => {
+                let Some(path) = rfd::FileDialog::new()
+                    .add_filter(kind.label(), kind.extensions())
+                    .pick_file()
+                else {
+                    return;
+                };
+                match self.media.add_file(&path, kind) {
+                    Ok(name) => *card.slot_mut(slot) = Some(name),
+                    Err(e) => self.show_message(format!("Failed to add {}: {}", kind.label(), e)),
+                }
+            }
+        }
+    }
+
+    /// Plays a stored audio clip, stopping the one still playing
+    pub(super) fn play_audio(&mut self, name: &str) {
+        let played = self
+            .media
+            .path(name)
+            .and_then(|path| self.audio_player.play(&path));
+        if let Err(e) = played {
+            self.show_message(format!("Failed to play audio: {}", e));
+        }
+    }
+}
@@ -0,0 +1,1343 @@
+//! Main application UI and state management.
+//! Handles the flashcard app interface, deck management, and learning sessions.
+
+mod import;
+mod markdown;
+mod media;
+mod statistics;
+mod tags;
+
+use crate::audio::AudioPlayer;
+use crate::database::db;
+use crate::database::media::MediaStore;
+use crate::error::{FlashcardsError, Result};
+use crate::export::backup::Backup;
+use crate::models::answer::DiffKind;
+use crate::models::clock::learning_day;
+use crate::models::deck_tree::is_in_subtree;
+use crate::models::tag_filter::split_tags;
+use crate::models::{
+    AnswerCheck, AnswerOptions, ClockMode, DEFAULT_ROLLOVER_HOUR, DailyLimits, DeckNode, DeckSet,
+    DirectionMode, LearningSession, SchedulerKind, Statistics, StudiedToday, StudyMode,
+};
+use chrono::NaiveDate;
+use chrono::{DateTime, Local};
+use eframe::egui;
+use markdown::{markdown_editor, render_markdown};
+use media::{CARD_IMAGE_HEIGHT, CardMedia, MediaSlot, THUMBNAIL_HEIGHT, card_image, media_fields};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Application screen states
+#[derive(Default)]
+enum AppScreen {
+    #[default]
+    Main,
+    LearningSession,
+    Statistics,
+}
+
+/// Flashcard being changed in the edit dialog
+#[derive(Clone)]
+struct FlashcardEdit {
+    id: i64,
+    term: String,
+    definition: String,
+    deck_name: String,
+    /// None follows the deck setting
+    directions: Option<DirectionMode>,
+    /// Tags separated by spaces or commas
+    tags: String,
+    media: CardMedia,
+}
+
+/// Settings of the selected deck, loaded when the selection changes
+/// so they are not queried on every frame
+#[derive(Clone, Copy, Default)]
+struct DeckSettings {
+    scheduler: SchedulerKind,
+    directions: DirectionMode,
+    limits: DailyLimits,
+    studied: StudiedToday,
+}
+
+/// Deletion waiting for confirmation
+enum PendingDelete {
+    Deck(String),
+    Flashcard { id: i64, term: String },
+}
+
+/// Main application state
+#[derive(Default)]
+pub struct MyApp {
+    show_confirmation_dialog: bool,
+    allowed_to_close: bool,
+    all_decks: DeckSet,
+    selected_deck_index: Option<usize>,
+    deck_settings: DeckSettings,
+    /// Due cards per deck, reloaded when cards, reviews or the learning day change
+    due_counts: HashMap<String, usize>,
+    /// Learning day `due_counts` and `deck_settings` were loaded on; None reloads them
+    /// on the next frame
+    loaded_day: Option<NaiveDate>,
+    current_term: String,
+    current_definition: String,
+    current_tags: String,
+    current_media: CardMedia,
+    new_deck_name: String,
+    conn: Option<Arc<Mutex<Connection>>>,
+    /// Store of the card images and audio clips of the database
+    media: MediaStore,
+    audio_player: AudioPlayer,
+
+    current_screen: AppScreen,
+    learning_session: Option<LearningSession>,
+    statistics: Option<Statistics>,
+
+    current_date_display: String,
+    clock_mode: ClockMode,
+    rollover_hour: u32,
+    study_mode: StudyMode,
+    answer_options: AnswerOptions,
+    /// Play the answer's audio when it is revealed
+    auto_play_audio: bool,
+    show_settings_dialog: bool,
+
+    show_export_dialog: bool,
+    show_import_result_dialog: bool,
+    import_result_message: String,
+    csv_import: Option<import::CsvImport>,
+    anki_import: Option<import::AnkiImport>,
+    merge_import: Option<import::MergeImport>,
+    pending_restore: Option<Backup>,
+    tag_study: Option<tags::TagStudy>,
+
+    editing_flashcard: Option<FlashcardEdit>,
+    /// Deck being renamed and the new name typed so far
+    renaming_deck: Option<(String, String)>,
+    pending_delete: Option<PendingDelete>,
+}
+
+/// Quality grades with their button labels
+const GRADES: [(u8, &str); 6] = [
+    (0, "Blackout"),
+    (1, "Wrong"),
+    (2, "Wrong (familiar)"),
+    (3, "Difficult"),
+    (4, "Correct"),
+    (5, "Perfect"),
+];
+
+/// Colors the difference between a typed and the expected answer:
+/// missing characters in green, wrongly typed ones struck through in red
+fn diff_layout(check: &AnswerCheck) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    for segment in &check.diff {
+        let format = match segment.kind {
+            DiffKind::Same => egui::TextFormat::default(),
+            DiffKind::Missing => egui::TextFormat {
+                color: egui::Color32::from_rgb(0, 160, 0),
+                underline: egui::Stroke::new(1.0, egui::Color32::from_rgb(0, 160, 0)),
+                ..Default::default()
+            },
+            DiffKind::Extra => egui::TextFormat {
+                color: egui::Color32::RED,
+                strikethrough: egui::Stroke::new(1.0, egui::Color32::RED),
+                ..Default::default()
+            },
+        };
+        job.append(&segment.text, 0.0, format);
+    }
+    job
+}
+
+/// Renders deck tree nodes, collapsing the ones with sub-decks
+fn render_deck_nodes(
+    ui: &mut egui::Ui,
+    nodes: &[DeckNode],
+    selected: Option<usize>,
+    action_select: &mut Option<usize>,
+    action_learn: &mut Option<String>,
+) {
+    for node in nodes {
+        let row = |ui: &mut egui::Ui,
+                   action_select: &mut Option<usize>,
+                   action_learn: &mut Option<String>| {
+            let text = format!(
+                "{} ({} cards, {} due)",
+                node.label(),
+                node.card_count,
+                node.due_count
+            );
+            match node.deck_index {
+                Some(index) => {
+                    if ui.selectable_label(selected == Some(index), text).clicked() {
+                        *action_select = Some(index);
+                    }
+                }
+                // A level without its own deck cannot hold cards
+                None => {
+                    ui.label(text);
+                }
+            }
+            if ui.button("Learn").clicked() {
+                *action_learn = Some(node.name.clone());
+            }
+        };
+
+        if node.children.is_empty() {
+            ui.horizontal(|ui| row(ui, action_select, action_learn));
+        } else {
+            let id = ui.make_persistent_id(("deck_node", &node.name));
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+                .show_header(ui, |ui| row(ui, action_select, action_learn))
+                .body(|ui| {
+                    render_deck_nodes(ui, &node.children, selected, action_select, action_learn)
+                });
+        }
+    }
+}
+
+/// Formats SystemTime as YYYY-MM-DD string
+fn format_system_time(time: SystemTime) -> String {
+    let datetime: DateTime<Local> = time.into();
+    datetime.format("%Y-%m-%d").to_string()
+}
+
+impl eframe::App for MyApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        match self.current_screen {
+            AppScreen::Main => self.render_main_screen(ctx),
+            AppScreen::LearningSession => self.render_learning_screen(ctx),
+            AppScreen::Statistics => self.render_statistics_screen(ctx),
+        }
+        self.render_edit_dialogs(ctx);
+        self.render_settings_dialog(ctx);
+        self.render_csv_import_dialog(ctx);
+        self.render_anki_import_dialog(ctx);
+        self.render_merge_dialog(ctx);
+        self.render_restore_dialog(ctx);
+        self.render_tag_study_dialog(ctx);
+
+        // Handle window close requests with confirmation dialog
+        if ctx.input(|i| i.viewport().close_requested()) {
+            if self.allowed_to_close {
+                // Allow close
+            } else {
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                self.show_confirmation_dialog = true;
+            }
+        }
+
+        if self.show_confirmation_dialog {
+            egui::Window::new("Do you want to quit?")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.button("No").clicked() {
+                            self.show_confirmation_dialog = false;
+                            self.allowed_to_close = false;
+                        }
+
+                        if ui.button("Yes").clicked() {
+                            self.show_confirmation_dialog = false;
+                            self.allowed_to_close = true;
+                            ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                    });
+                });
+        }
+        // exporting a deck
+        if self.show_export_dialog {
+            let mut export_deck_index: Option<usize> = None;
+            let mut should_cancel = false;
+
+            egui::Window::new("Export Deck")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("Select a deck to export:");
+                    ui.separator();
+
+                    for (i, deck) in self.all_decks.decks.iter().enumerate() {
+                        if ui
+                            .button(format!("{} ({} cards)", deck.name, deck.flashcards.len()))
+                            .clicked()
+                        {
+                            export_deck_index = Some(i);
+                        }
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Cancel").clicked() {
+                        should_cancel = true;
+                    }
+                });
+
+            if let Some(i) = export_deck_index {
+                self.handle_export(i);
+            }
+            if should_cancel {
+                self.show_export_dialog = false;
+            }
+        }
+
+        if self.show_import_result_dialog {
+            egui::Window::new("Result")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(&self.import_result_message);
+                    ui.add_space(10.0);
+                    if ui.button("OK").clicked() {
+                        self.show_import_result_dialog = false;
+                    }
+                });
+        }
+    }
+}
+
+impl MyApp {
+    /// Creates a new application instance with decks loaded from database
+    pub fn new_with_deckset(deckset: DeckSet, conn: Connection) -> Self {
+        let current_date = db::get_current_date(&conn)
+            .map(|d| format!("{:?}", d))
+            .unwrap_or_else(|_| "Unknown".to_string());
+        let clock_mode = db::get_clock_mode(&conn).unwrap_or_default();
+        let rollover_hour = db::get_rollover_hour(&conn).unwrap_or(DEFAULT_ROLLOVER_HOUR);
+        let study_mode = db::get_study_mode(&conn).unwrap_or_default();
+        let answer_options = db::get_answer_options(&conn).unwrap_or_default();
+        let auto_play_audio = db::get_auto_play_audio(&conn).unwrap_or_default();
+        let has_decks = !deckset.decks.is_empty();
+        let media = MediaStore::for_connection(&conn);
+        let mut app = Self {
+            all_decks: deckset,
+            selected_deck_index: if has_decks { Some(0) } else { None },
+            deck_settings: DeckSettings::default(),
+            due_counts: HashMap::new(),
+            loaded_day: None,
+            current_term: String::new(),
+            current_definition: String::new(),
+            current_tags: String::new(),
+            current_media: CardMedia::default(),
+            new_deck_name: String::new(),
+            show_confirmation_dialog: false,
+            allowed_to_close: false,
+            conn: Some(Arc::new(Mutex::new(conn))),
+            media,
+            audio_player: AudioPlayer::default(),
+            current_screen: AppScreen::Main,
+            learning_session: None,
+            statistics: None,
+            current_date_display: current_date,
+            clock_mode,
+            rollover_hour,
+            study_mode,
+            answer_options,
+            auto_play_audio,
+            show_settings_dialog: false,
+            show_export_dialog: false,
+            show_import_result_dialog: false,
+            import_result_message: String::new(),
+            csv_import: None,
+            anki_import: None,
+            merge_import: None,
+            pending_restore: None,
+            tag_study: None,
+            editing_flashcard: None,
+            renaming_deck: None,
+            pending_delete: None,
+        };
+        app.load_deck_settings();
+        app
+    }
+
+    /// Shows a message in the result dialog
+    fn show_message(&mut self, message: impl Into<String>) {
+        self.import_result_message = message.into();
+        self.show_import_result_dialog = true;
+    }
+
+    /// Runs a database operation, showing the error in the result dialog if it fails
+    fn with_db<T>(&mut self, operation: impl FnOnce(&Connection) -> Result<T>) -> Option<T> {
+        let conn = self.conn.as_ref()?;
+        let result = conn
+            .lock()
+            .map_err(FlashcardsError::from)
+            .and_then(|conn| operation(&conn));
+
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.show_message(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Reloads all decks from the database and selects the named deck
+    ///
+    /// Falls back to the first deck if the named deck no longer exists.
+    fn reload_decks(&mut self, selected: Option<&str>) {
+        if let Some(deckset) = self.with_db(db::load_all_decks) {
+            self.all_decks = deckset;
+        }
+
+        self.load_due_counts();
+
+        let decks = &self.all_decks.decks;
+        let index = selected
+            .and_then(|name| decks.iter().position(|d| d.name == name))
+            .or(if decks.is_empty() { None } else { Some(0) });
+        self.select_deck(index);
+    }
+
+    /// Selects a deck and loads its settings
+    fn select_deck(&mut self, index: Option<usize>) {
+        self.selected_deck_index = index;
+        self.load_deck_settings();
+    }
+
+    /// Loads the settings of the selected deck from the database
+    fn load_deck_settings(&mut self) {
+        let Some(deck_name) = self.selected_deck_name() else {
+            self.deck_settings = DeckSettings::default();
+            return;
+        };
+        if let Some(settings) = self.with_db(|conn| {
+            Ok(DeckSettings {
+                scheduler: db::get_deck_scheduler(&deck_name, conn)?,
+                directions: db::get_deck_directions(&deck_name, conn)?,
+                limits: db::get_deck_limits(&deck_name, conn)?,
+                studied: db::get_studied_today(&deck_name, conn)?,
+            })
+        }) {
+            self.deck_settings = settings;
+        }
+    }
+
+    /// Loads the number of due cards of every deck from the database
+    fn load_due_counts(&mut self) {
+        if let Some(counts) = self.with_db(db::get_due_counts) {
+            self.due_counts = counts;
+        }
+    }
+
+    /// Name of the currently selected deck
+    fn selected_deck_name(&self) -> Option<String> {
+        self.selected_deck_index
+            .and_then(|i| self.all_decks.decks.get(i))
+            .map(|deck| deck.name.clone())
+    }
+
+    /// Opens the edit dialog for a flashcard of a deck
+    fn edit_flashcard(&mut self, deck_name: &str, term: &str) {
+        let found = self.with_db(|conn| {
+            let id = db::find_flashcard_id(deck_name, term, conn)?
+                .ok_or_else(|| FlashcardsError::NotFound(format!("Flashcard '{}'", term)))?;
+            let (deck_name, flashcard) = db::get_flashcard(id, conn)?;
+            Ok(FlashcardEdit {
+                id,
+                media: CardMedia::of(&flashcard),
+                term: flashcard.term,
+                definition: flashcard.definition,
+                deck_name,
+                directions: db::get_flashcard_directions(id, conn)?,
+                tags: flashcard.tags.join(" "),
+            })
+        });
+        self.editing_flashcard = found;
+    }
+
+    /// Asks for confirmation before deleting a flashcard of a deck
+    fn confirm_delete_flashcard(&mut self, deck_name: &str, term: &str) {
+        let found = self.with_db(|conn| {
+            db::find_flashcard_id(deck_name, term, conn)?
+                .ok_or_else(|| FlashcardsError::NotFound(format!("Flashcard '{}'", term)))
+        });
+        if let Some(id) = found {
+            self.pending_delete = Some(PendingDelete::Flashcard {
+                id,
+                term: term.to_string(),
+            });
+        }
+    }
+
+    /// Renders the edit, rename and delete confirmation dialogs
+    fn render_edit_dialogs(&mut self, ctx: &egui::Context) {
+        if let Some(edit) = &mut self.editing_flashcard {
+            let mut save = false;
+            let mut cancel = false;
+            let mut media_action = None;
+
+            egui::Window::new("Edit Flashcard")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    markdown_editor(ui, "Term:", &mut edit.term);
+                    let term_action = media_fields(ui, &edit.media, MediaSlot::TERM, &self.media);
+                    markdown_editor(ui, "Definition:", &mut edit.definition);
+                    media_action =
+                        media_fields(ui, &edit.media, MediaSlot::DEFINITION, &self.media)
+                            .or(term_action);
+                    ui.horizontal(|ui| {
+                        ui.label("Deck:");
+                        egui::ComboBox::from_id_salt("edit_flashcard_deck")
+                            .selected_text(edit.deck_name.clone())
+                            .show_ui(ui, |ui| {
+                                for deck in &self.all_decks.decks {
+                                    ui.selectable_value(
+                                        &mut edit.deck_name,
+                                        deck.name.clone(),
+                                        &deck.name,
+                                    );
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Directions:");
+                        egui::ComboBox::from_id_salt("edit_flashcard_directions")
+                            .selected_text(edit.directions.map_or("Deck default", |m| m.label()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut edit.directions, None, "Deck default");
+                                for mode in DirectionMode::ALL {
+                                    ui.selectable_value(
+                                        &mut edit.directions,
+                                        Some(mode),
+                                        mode.label(),
+                                    );
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Tags:");
+                        ui.text_edit_singleline(&mut edit.tags);
+                    });
+
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            save = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+
+            if let Some((slot, action)) = media_action
+                && let Some(mut media) = self.editing_flashcard.as_ref().map(|e| e.media.clone())
+            {
+                self.apply_media_action(&mut media, slot, action);
+                if let Some(edit) = &mut self.editing_flashcard {
+                    edit.media = media;
+                }
+            }
+            if save && let Some(edit) = self.editing_flashcard.clone() {
+                let saved = self.with_db(|conn| {
+                    let tx = conn.unchecked_transaction()?;
+                    db::update_flashcard(edit.id, &edit.term, &edit.definition, &tx)?;
+                    db::move_flashcard(edit.id, &edit.deck_name, &tx)?;
+                    db::set_flashcard_directions(edit.id, edit.directions, &tx)?;
+                    db::set_flashcard_tags(edit.id, &split_tags(&edit.tags), &tx)?;
+                    edit.media.save(edit.id, &tx)?;
+                    tx.commit()?;
+                    Ok(())
+                });
+                if saved.is_some() {
+                    self.editing_flashcard = None;
+                    let selected = self.selected_deck_name();
+                    self.reload_decks(selected.as_deref());
+                }
+            }
+            if cancel {
+                self.editing_flashcard = None;
+            }
+        }
+
+        if let Some((old_name, new_name)) = &mut self.renaming_deck {
+            let mut rename = false;
+            let mut cancel = false;
+
+            egui::Window::new(format!("Rename Deck '{}'", old_name))
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("New name:");
+                        ui.text_edit_singleline(new_name);
+                    });
+
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Rename").clicked() {
+                            rename = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+
+            if rename && let Some((old_name, new_name)) = self.renaming_deck.clone() {
+                let new_name = new_name.trim().to_string();
+                if self
+                    .with_db(|conn| db::rename_deck(&old_name, &new_name, conn))
+                    .is_some()
+                {
+                    self.renaming_deck = None;
+                    self.reload_decks(Some(&new_name));
+                }
+            }
+            if cancel {
+                self.renaming_deck = None;
+            }
+        }
+
+        if let Some(pending) = &self.pending_delete {
+            let question = match pending {
+                PendingDelete::Deck(name) => format!(
+                    "Delete deck '{}' with all of its sub-decks, flashcards and review progress?",
+                    name
+                ),
+                PendingDelete::Flashcard { term, .. } => {
+                    format!("Delete flashcard '{}' and its review progress?", term)
+                }
+            };
+            let mut confirm = false;
+            let mut cancel = false;
+
+            egui::Window::new("Confirm Delete")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(question);
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            confirm = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+
+            if confirm && let Some(pending) = self.pending_delete.take() {
+                let selected = self.selected_deck_name();
+                match pending {
+                    PendingDelete::Deck(name) => {
+                        if self.with_db(|conn| db::delete_deck(&name, conn)).is_some() {
+                            self.reload_decks(None);
+                        }
+                    }
+                    PendingDelete::Flashcard { id, .. } => {
+                        if self
+                            .with_db(|conn| db::delete_flashcard(id, conn))
+                            .is_some()
+                        {
+                            self.reload_decks(selected.as_deref());
+                        }
+                    }
+                }
+            }
+            if cancel {
+                self.pending_delete = None;
+            }
+        }
+    }
+
+    /// Renders the settings dialog
+    fn render_settings_dialog(&mut self, ctx: &egui::Context) {
+        if !self.show_settings_dialog {
+            return;
+        }
+
+        let mut clock_mode = self.clock_mode;
+        let mut rollover_hour = self.rollover_hour;
+        let mut study_mode = self.study_mode;
+        let mut answer_options = self.answer_options;
+        let mut auto_play_audio = self.auto_play_audio;
+        let mut close = false;
+
+        egui::Window::new("Settings")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Clock:");
+                    egui::ComboBox::from_id_salt("clock_mode")
+                        .selected_text(clock_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in ClockMode::ALL {
+                                ui.selectable_value(&mut clock_mode, mode, mode.label());
+                            }
+                        });
+                });
+                ui.label("The simulated clock only moves when \"Next Day\" is clicked.");
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.label("New day starts at:");
+                    ui.add(
+                        egui::DragValue::new(&mut rollover_hour)
+                            .range(0..=23)
+                            .suffix(":00"),
+                    );
+                });
+                ui.label("All cards due on a day are available from this hour on.");
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.label("Study mode:");
+                    egui::ComboBox::from_id_salt("study_mode")
+                        .selected_text(study_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in StudyMode::ALL {
+                                ui.selectable_value(&mut study_mode, mode, mode.label());
+                            }
+                        });
+                });
+                ui.label("Typed answers:");
+                ui.checkbox(&mut answer_options.ignore_case, "Ignore case");
+                ui.checkbox(&mut answer_options.ignore_diacritics, "Ignore diacritics");
+                ui.horizontal(|ui| {
+                    ui.label("Typos tolerated:");
+                    ui.add(egui::DragValue::new(&mut answer_options.typo_tolerance).range(0..=5));
+                });
+                ui.label("Separate several accepted answers with \";\".");
+
+                ui.add_space(10.0);
+                ui.checkbox(
+                    &mut auto_play_audio,
+                    "Play audio when the answer is revealed",
+                );
+
+                ui.add_space(10.0);
+                if ui.button("Close").clicked() {
+                    close = true;
+                }
+            });
+
+        if clock_mode != self.clock_mode
+            && self
+                .with_db(|conn| db::set_clock_mode(clock_mode, conn))
+                .is_some()
+        {
+            self.clock_mode = clock_mode;
+            self.loaded_day = None;
+        }
+        if rollover_hour != self.rollover_hour
+            && self
+                .with_db(|conn| db::set_rollover_hour(rollover_hour, conn))
+                .is_some()
+        {
+            self.rollover_hour = rollover_hour;
+            self.loaded_day = None;
+        }
+        if study_mode != self.study_mode
+            && self
+                .with_db(|conn| db::set_study_mode(study_mode, conn))
+                .is_some()
+        {
+            self.study_mode = study_mode;
+        }
+        if answer_options != self.answer_options
+            && self
+                .with_db(|conn| db::set_answer_options(&answer_options, conn))
+                .is_some()
+        {
+            self.answer_options = answer_options;
+        }
+        if auto_play_audio != self.auto_play_audio
+            && self
+                .with_db(|conn| db::set_auto_play_audio(auto_play_audio, conn))
+                .is_some()
+        {
+            self.auto_play_audio = auto_play_audio;
+        }
+        if close {
+            self.show_settings_dialog = false;
+        }
+    }
+
+    /// Renders the main screen with deck management interface
+    fn render_main_screen(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let mut today = None;
+            ui.horizontal(|ui| {
+                // Fetch and display current date from database
+                if let Some(conn) = &self.conn
+                    && let Ok(conn_guard) = conn.lock()
+                    && let Ok(current_date) = db::get_current_date(&conn_guard)
+                {
+                    self.current_date_display = format_system_time(current_date);
+                    today = Some(learning_day(current_date, self.rollover_hour));
+                }
+                ui.label(self.current_date_display.to_string());
+
+                // Days only move by hand with the simulated clock
+                if self.clock_mode == ClockMode::Simulated
+                    && ui.button("Next Day").clicked()
+                    && let Some(conn) = &self.conn
+                {
+                    let conn = conn.lock().unwrap();
+                    let _ = db::advance_day(&conn);
+                    if let Ok(current_date) = db::get_current_date(&conn) {
+                        self.current_date_display = format_system_time(current_date);
+                    }
+                }
+
+                if ui.button("Settings").clicked() {
+                    self.show_settings_dialog = true;
+                }
+                if ui.button("Statistics").clicked() {
+                    self.open_statistics();
+                }
+            });
+            ui.separator();
+
+            // Import/Export buttons
+            ui.horizontal(|ui| {
+                if ui.button("Export Deck").clicked() {
+                    self.show_export_dialog = true;
+                }
+                if ui.button("Import Deck").clicked() {
+                    self.handle_import();
+                }
+                ui.separator();
+                if ui.button("Backup").clicked() {
+                    self.handle_backup();
+                }
+                if ui.button("Restore").clicked() {
+                    self.handle_restore();
+                }
+            });
+
+            ui.separator();
+
+            // Deck creation section
+            ui.heading("Create New Deck");
+            ui.horizontal(|ui| {
+                ui.label("Deck name:");
+                ui.text_edit_singleline(&mut self.new_deck_name);
+                if ui.button("Create Deck").clicked() {
+                    let name = self.new_deck_name.trim().to_string();
+                    if self.with_db(|conn| db::new_deck(&name, conn)).is_some() {
+                        self.new_deck_name.clear();
+                        self.reload_decks(Some(&name));
+                    }
+                }
+            });
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.heading(format!("Decks ({})", self.all_decks.decks.len()));
+                if ui.button("Study by Tags").clicked() {
+                    self.open_tag_study();
+                }
+            });
+
+            // Cards become due when a new learning day starts
+            if today.is_some() && today != self.loaded_day {
+                self.loaded_day = today;
+                self.load_due_counts();
+                self.load_deck_settings();
+            }
+            let tree = DeckNode::build(&self.all_decks, &self.due_counts);
+
+            // We store actions to execute after UI rendering to avoid borrowing conflicts
+            let mut action_select: Option<usize> = None;
+            let mut action_learn: Option<String> = None;
+
+            egui::ScrollArea::vertical()
+                .id_salt("decks_list")
+                .max_height(150.0)
+                .show(ui, |ui| {
+                    render_deck_nodes(
+                        ui,
+                        &tree,
+                        self.selected_deck_index,
+                        &mut action_select,
+                        &mut action_learn,
+                    );
+                });
+
+            // Execute deferred actions
+            if let Some(i) = action_select {
+                self.select_deck(Some(i));
+            }
+            if let Some(name) = action_learn {
+                self.start_learning_session(&name);
+            }
+
+            ui.separator();
+
+            // Flashcard management for selected deck
+            let mut action_add = false;
+            let mut media_action = None;
+            let mut action_rename_deck: Option<String> = None;
+            let mut action_delete_deck: Option<String> = None;
+            let mut action_edit: Option<(String, String)> = None;
+            let mut action_delete: Option<(String, String)> = None;
+            let mut action_scheduler: Option<(String, SchedulerKind)> = None;
+            let mut action_directions: Option<(String, DirectionMode)> = None;
+            let mut action_limits: Option<String> = None;
+
+            if let Some(deck_index) = self.selected_deck_index {
+                if let Some(current_deck) = self.all_decks.decks.get(deck_index) {
+                    ui.horizontal(|ui| {
+                        ui.heading(format!("Selected Deck: {}", current_deck.name));
+                        if ui.button("Rename").clicked() {
+                            action_rename_deck = Some(current_deck.name.clone());
+                        }
+                        if ui.button("Delete").clicked() {
+                            action_delete_deck = Some(current_deck.name.clone());
+                        }
+                    });
+
+                    // Scheduler used when learning this deck
+                    let mut scheduler = self.deck_settings.scheduler;
+                    ui.horizontal(|ui| {
+                        ui.label("Scheduler:");
+                        egui::ComboBox::from_id_salt("deck_scheduler")
+                            .selected_text(scheduler.label())
+                            .show_ui(ui, |ui| {
+                                for kind in SchedulerKind::ALL {
+                                    ui.selectable_value(&mut scheduler, kind, kind.label());
+                                }
+                            });
+                    });
+
+                    if scheduler != self.deck_settings.scheduler {
+                        action_scheduler = Some((current_deck.name.clone(), scheduler));
+                    }
+
+                    // Directions studied, each scheduled separately
+                    let mut directions = self.deck_settings.directions;
+                    ui.horizontal(|ui| {
+                        ui.label("Directions:");
+                        egui::ComboBox::from_id_salt("deck_directions")
+                            .selected_text(directions.label())
+                            .show_ui(ui, |ui| {
+                                for mode in DirectionMode::ALL {
+                                    ui.selectable_value(&mut directions, mode, mode.label());
+                                }
+                            });
+                    });
+
+                    if directions != self.deck_settings.directions {
+                        action_directions = Some((current_deck.name.clone(), directions));
+                    }
+
+                    // Daily limits, with today's progress towards them, saved when an
+                    // edit ends rather than on every frame of a drag
+                    let studied = self.deck_settings.studied;
+                    let limits = &mut self.deck_settings.limits;
+                    let mut edited = false;
+                    ui.horizontal(|ui| {
+                        ui.label("New cards/day:");
+                        let response =
+                            ui.add(egui::DragValue::new(&mut limits.new_cards).range(0..=9999));
+                        edited |= response.drag_stopped() || response.lost_focus();
+                        ui.label(format!("({} today)", studied.new_cards));
+                        ui.label("Reviews/day:");
+                        let response =
+                            ui.add(egui::DragValue::new(&mut limits.reviews).range(0..=9999));
+                        edited |= response.drag_stopped() || response.lost_focus();
+                        ui.label(format!("({} today)", studied.reviews));
+                    });
+
+                    if edited {
+                        action_limits = Some(current_deck.name.clone());
+                    }
+
+                    markdown_editor(ui, "Term:", &mut self.current_term);
+                    let term_action =
+                        media_fields(ui, &self.current_media, MediaSlot::TERM, &self.media);
+                    markdown_editor(ui, "Definition:", &mut self.current_definition);
+                    media_action =
+                        media_fields(ui, &self.current_media, MediaSlot::DEFINITION, &self.media)
+                            .or(term_action);
+
+                    ui.horizontal(|ui| {
+                        ui.label("Tags:");
+                        ui.text_edit_singleline(&mut self.current_tags);
+                    });
+                    if ui.button("Add Flashcard").clicked() {
+                        action_add = true;
+                    }
+
+                    ui.separator();
+
+                    ui.heading(format!("Flashcards ({})", current_deck.flashcards.len()));
+
+                    egui::ScrollArea::vertical()
+                        .id_salt("flashcards_list")
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            for (i, flashcard) in current_deck.flashcards.iter().enumerate() {
+                                ui.group(|ui| {
+                                    ui.label(format!("{}. Term:", i + 1));
+                                    render_markdown(ui, &flashcard.term);
+                                    if let Some(name) = &flashcard.term_image {
+                                        card_image(ui, &self.media, name, THUMBNAIL_HEIGHT);
+                                    }
+                                    ui.label("   Definition:");
+                                    render_markdown(ui, &flashcard.definition);
+                                    if let Some(name) = &flashcard.definition_image {
+                                        card_image(ui, &self.media, name, THUMBNAIL_HEIGHT);
+                                    }
+                                    if !flashcard.tags.is_empty() {
+                                        ui.label(format!("   Tags: {}", flashcard.tags.join(" ")));
+                                    }
+                                    ui.horizontal(|ui| {
+                                        if ui.button("Edit").clicked() {
+                                            action_edit = Some((
+                                                current_deck.name.clone(),
+                                                flashcard.term.clone(),
+                                            ));
+                                        }
+                                        if ui.button("Delete").clicked() {
+                                            action_delete = Some((
+                                                current_deck.name.clone(),
+                                                flashcard.term.clone(),
+                                            ));
+                                        }
+                                    });
+                                });
+                            }
+                        });
+                }
+            } else {
+                ui.label("Select a deck to add flashcards");
+            }
+
+            // Execute deferred actions
+            if let Some((deck_name, scheduler)) = action_scheduler
+                && self
+                    .with_db(|conn| db::set_deck_scheduler(&deck_name, scheduler, conn))
+                    .is_some()
+            {
+                self.deck_settings.scheduler = scheduler;
+            }
+            if let Some((deck_name, directions)) = action_directions
+                && self
+                    .with_db(|conn| db::set_deck_directions(&deck_name, directions, conn))
+                    .is_some()
+            {
+                self.deck_settings.directions = directions;
+                self.load_due_counts();
+            }
+            if let Some(deck_name) = action_limits {
+                let limits = self.deck_settings.limits;
+                if self
+                    .with_db(|conn| db::set_deck_limits(&deck_name, &limits, conn))
+                    .is_none()
+                {
+                    // Show the limits that are actually saved
+                    self.load_deck_settings();
+                }
+            }
+            if let Some((slot, action)) = media_action {
+                let mut media = std::mem::take(&mut self.current_media);
+                self.apply_media_action(&mut media, slot, action);
+                self.current_media = media;
+            }
+            if action_add && let Some(deck_name) = self.selected_deck_name() {
+                let term = self.current_term.trim().to_string();
+                let definition = self.current_definition.trim().to_string();
+                let tags = split_tags(&self.current_tags);
+                let media = self.current_media.clone();
+                if self
+                    .with_db(|conn| {
+                        let tx = conn.unchecked_transaction()?;
+                        let id = db::add_flashcard(&deck_name, &term, &definition, &tx)?;
+                        if !tags.is_empty() {
+                            db::set_flashcard_tags(id, &tags, &tx)?;
+                        }
+                        media.save(id, &tx)?;
+                        tx.commit()?;
+                        Ok(())
+                    })
+                    .is_some()
+                {
+                    self.current_term.clear();
+                    self.current_definition.clear();
+                    self.current_tags.clear();
+                    self.current_media = CardMedia::default();
+                    self.reload_decks(Some(&deck_name));
+                }
+            }
+            if let Some(name) = action_rename_deck {
+                self.renaming_deck = Some((name.clone(), name));
+            }
+            if let Some(name) = action_delete_deck {
+                self.pending_delete = Some(PendingDelete::Deck(name));
+            }
+            if let Some((deck_name, term)) = action_edit {
+                self.edit_flashcard(&deck_name, &term);
+            }
+            if let Some((deck_name, term)) = action_delete {
+                self.confirm_delete_flashcard(&deck_name, &term);
+            }
+        });
+    }
+
+    /// Renders the learning session screen with flashcard review interface
+    fn render_learning_screen(&mut self, ctx: &egui::Context) {
+        // Audio is played once the session is no longer borrowed
+        let mut play_audio: Option<String> = None;
+        let mut stop_audio = false;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(session) = &mut self.learning_session {
+                ui.heading(format!("Learning: {}", session.deck_name));
+
+                ui.label(session.phase_message());
+
+                ui.label(format!(
+                    "Progress: {} / {} learned ({} remaining)",
+                    session.learned_count(),
+                    session.total_count(),
+                    session.remaining_count()
+                ));
+
+                ui.add_space(20.0);
+
+                if session.is_completed() {
+                    ui.heading("Congratulations!");
+                    ui.label("You've learned all cards in this deck!");
+
+                    ui.add_space(20.0);
+
+                    if ui.button("Back to Main Screen").clicked() {
+                        self.current_screen = AppScreen::Main;
+                        self.learning_session = None;
+                        // Reviews changed the due counts and today's progress
+                        self.loaded_day = None;
+                    }
+                } else if let Some(card) = session.current_card() {
+                    // Clone values to avoid borrowing issues
+                    let show_def = session.show_definition;
+                    let typed_mode = session.mode == StudyMode::Typed;
+                    let is_learned = card.is_learned;
+                    let prompt_label = card.prompt_label();
+                    let answer_label = card.answer_label();
+                    let prompt = card.prompt().to_string();
+                    let answer = card.answer().to_string();
+                    let prompt_image = card.prompt_image().map(str::to_string);
+                    let answer_image = card.answer_image().map(str::to_string);
+                    let prompt_audio = card.prompt_audio().map(str::to_string);
+                    let answer_audio = card.answer_audio().map(str::to_string);
+                    let choices = match session.mode {
+                        StudyMode::MultipleChoice => session.current_choices().cloned(),
+                        _ => None,
+                    };
+                    let selected_choice = session.selected_choice;
+
+                    let mut check_typed = false;
+                    let mut action_choose: Option<usize> = None;
+                    let mut action_play: Option<String> = None;
+                    ui.group(|ui| {
+                        ui.set_min_height(200.0);
+                        ui.vertical_centered(|ui| {
+                            ui.add_space(20.0);
+
+                            ui.heading(format!("{}:", prompt_label));
+                            render_markdown(ui, &prompt);
+                            if let Some(name) = &prompt_image {
+                                card_image(ui, &self.media, name, CARD_IMAGE_HEIGHT);
+                            }
+                            if let Some(name) = &prompt_audio
+                                && ui.button("▶ Play").clicked()
+                            {
+                                action_play = Some(name.clone());
+                            }
+
+                            ui.add_space(20.0);
+
+                            if let Some(question) = &choices {
+                                for (i, choice) in question.choices.iter().enumerate() {
+                                    let mut button = egui::Button::new(choice);
+                                    if selected_choice.is_some() && i == question.correct_index {
+                                        button = button.fill(egui::Color32::from_rgb(0, 120, 0));
+                                    } else if selected_choice == Some(i) {
+                                        button = button.fill(egui::Color32::from_rgb(160, 0, 0));
+                                    }
+                                    if ui.add(button).clicked() && selected_choice.is_none() {
+                                        action_choose = Some(i);
+                                    }
+                                }
+                            } else if show_def {
+                                ui.heading(format!("{}:", answer_label));
+                                render_markdown(ui, &answer);
+                                if let Some(name) = &answer_image {
+                                    card_image(ui, &self.media, name, CARD_IMAGE_HEIGHT);
+                                }
+                                if let Some(name) = &answer_audio
+                                    && ui.button("▶ Play").clicked()
+                                {
+                                    action_play = Some(name.clone());
+                                }
+                                if let Some(check) = &session.answer_check {
+                                    ui.add_space(10.0);
+                                    ui.label(format!("You typed: {}", session.typed_answer));
+                                    ui.label(diff_layout(check));
+                                }
+                            } else if typed_mode {
+                                let response = ui.text_edit_singleline(&mut session.typed_answer);
+                                if response.lost_focus()
+                                    && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                                {
+                                    check_typed = true;
+                                } else if !response.has_focus() {
+                                    response.request_focus();
+                                }
+                            } else {
+                                ui.label(format!("(Click 'Show {}' to reveal)", answer_label));
+                            }
+
+                            ui.add_space(20.0);
+                        });
+                    });
+
+                    ui.add_space(20.0);
+
+                    // Store actions to execute after UI rendering
+                    let mut action_toggle_def = false;
+                    let mut action_grade: Option<u8> = None;
+                    let mut action_back = false;
+
+                    if let Some(question) = &choices {
+                        // Picking a choice decides the grade
+                        if let Some(selected) = selected_choice
+                            && !is_learned
+                            && ui.button("Next").clicked()
+                        {
+                            action_grade = Some(question.quality_for(selected));
+                        }
+                    } else if !show_def && typed_mode {
+                        if ui.button("Check").clicked() {
+                            check_typed = true;
+                        }
+                    } else if !show_def && ui.button(format!("Show {}", answer_label)).clicked() {
+                        action_toggle_def = true;
+                    }
+
+                    // Quality rating buttons (0-5) - only show after revealing definition
+                    if show_def && !is_learned && choices.is_none() {
+                        let suggested = session.answer_check.as_ref().map(|c| c.suggested_quality);
+                        match suggested {
+                            Some(quality) => {
+                                ui.label(format!("Suggested grade: {} (click to accept)", quality))
+                            }
+                            None => ui.label("Rate your response:"),
+                        };
+                        for row in GRADES.chunks(3) {
+                            ui.horizontal(|ui| {
+                                for &(quality, label) in row {
+                                    let button =
+                                        egui::Button::new(format!("{} - {}", quality, label))
+                                            .selected(suggested == Some(quality));
+                                    if ui.add(button).clicked() {
+                                        action_grade = Some(quality);
+                                    }
+                                }
+                            });
+                        }
+                    }
+
+                    ui.add_space(20.0);
+
+                    if ui.button("Back to Main Screen").clicked() {
+                        action_back = true;
+                    }
+
+                    // Execute deferred actions
+                    if action_toggle_def {
+                        session.toggle_definition();
+                    }
+                    if check_typed {
+                        session.check_typed_answer(&self.answer_options);
+                    }
+                    if let Some(index) = action_choose {
+                        session.choose(index);
+                    }
+                    let revealed =
+                        (!show_def && session.show_definition) || action_choose.is_some();
+                    if revealed && self.auto_play_audio {
+                        play_audio = answer_audio.clone();
+                    }
+                    if action_play.is_some() {
+                        play_audio = action_play;
+                    }
+                    if let Some(quality) = action_grade {
+                        stop_audio = true;
+                        match session.grade_current_card(quality) {
+                            // After grading, move to next card
+                            Ok(()) => session.next_card(),
+                            Err(e) => {
+                                self.import_result_message =
+                                    format!("Failed to save review: {}", e);
+                                self.show_import_result_dialog = true;
+                            }
+                        }
+                    }
+                    if action_back {
+                        stop_audio = true;
+                        self.current_screen = AppScreen::Main;
+                        self.learning_session = None;
+                        // Reviews changed the due counts and today's progress
+                        self.loaded_day = None;
+                    }
+                }
+            }
+        });
+
+        if stop_audio {
+            self.audio_player.stop();
+        }
+        if let Some(name) = play_audio {
+            self.play_audio(&name);
+        }
+    }
+
+    /// Starts a learning session with cards due for review in a deck and its sub-decks
+    fn start_learning_session(&mut self, deck_name: &str) {
+        // Fetch only flashcards due today, within each deck's daily limits
+        let Some(due_cards) =
+            self.with_db(|conn| db::get_flashcards_for_deck_tree(deck_name, conn))
+        else {
+            return;
+        };
+        if due_cards.is_empty() {
+            self.show_message(format!("No cards left to study in '{}' today.", deck_name));
+            return;
+        }
+        let Some(conn) = &self.conn else {
+            return;
+        };
+
+        let mut session = LearningSession::new_from_several_decks(
+            deck_name.to_string(),
+            due_cards,
+            Arc::clone(conn),
+        );
+        session.mode = self.study_mode;
+        session.choice_pool = self
+            .all_decks
+            .decks
+            .iter()
+            .filter(|deck| is_in_subtree(&deck.name, deck_name))
+            .flat_map(|deck| deck.flashcards.iter().cloned())
+            .collect();
+        self.learning_session = Some(session);
+        self.current_screen = AppScreen::LearningSession;
+    }
+}
@@ -0,0 +1,184 @@
+//! Statistics screen showing how well the decks are known and how studying goes.
+//! The statistics are computed when the screen is opened; bar charts show a
+//! value per day or range, with the exact numbers on hover.
+
+use super::{AppScreen, MyApp};
+use crate::database::db;
+use crate::models::Statistics;
+use crate::models::statistics::STATISTICS_DAYS;
+use eframe::egui;
+
+const CHART_HEIGHT: f32 = 80.0;
+const BAR_WIDTH: f32 = 14.0;
+
+/// Draws a bar per value scaled to the largest one, showing label and value on hover
+fn bar_chart(ui: &mut egui::Ui, bars: &[(String, usize)]) {
+    let max = bars
+        .iter()
+        .map(|(_, value)| *value)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+        for (label, value) in bars {
+            let (rect, response) =
+                ui.allocate_exact_size(egui::vec2(BAR_WIDTH, CHART_HEIGHT), egui::Sense::hover());
+            let height = CHART_HEIGHT * *value as f32 / max as f32;
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(rect.left(), rect.bottom() - height),
+                rect.right_bottom(),
+            );
+            ui.painter()
+                .rect_filled(rect, 2.0, ui.visuals().faint_bg_color);
+            ui.painter()
+                .rect_filled(bar, 2.0, ui.visuals().selection.bg_fill);
+            response.on_hover_text(format!("{}: {}", label, value));
+        }
+    });
+}
+
+impl MyApp {
+    /// Computes the statistics and switches to the statistics screen
+    pub(super) fn open_statistics(&mut self) {
+        if let Some(statistics) = self.with_db(db::get_statistics) {
+            self.statistics = Some(statistics);
+            self.current_screen = AppScreen::Statistics;
+        }
+    }
+
+    /// Renders the statistics screen
+    pub(super) fn render_statistics_screen(&mut self, ctx: &egui::Context) {
+        let mut action_back = false;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Back").clicked() {
+                    action_back = true;
+                }
+                ui.heading("Statistics");
+            });
+            ui.separator();
+
+            let Some(stats) = &self.statistics else {
+                return;
+            };
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                render_statistics(ui, stats);
+            });
+        });
+
+        if action_back {
+            self.statistics = None;
+            self.current_screen = AppScreen::Main;
+        }
+    }
+}
+
+/// Renders every section of the statistics
+fn render_statistics(ui: &mut egui::Ui, stats: &Statistics) {
+    ui.heading("Cards");
+    egui::Grid::new("statistics_decks")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Deck");
+            ui.strong("New");
+            ui.strong("Learning");
+            ui.strong("Mature");
+            ui.end_row();
+
+            let mut totals = (0, 0, 0);
+            for deck in &stats.decks {
+                ui.label(&deck.deck_name);
+                ui.label(deck.new_cards.to_string());
+                ui.label(deck.learning.to_string());
+                ui.label(deck.mature.to_string());
+                ui.end_row();
+                totals.0 += deck.new_cards;
+                totals.1 += deck.learning;
+                totals.2 += deck.mature;
+            }
+
+            ui.strong("Total");
+            ui.strong(totals.0.to_string());
+            ui.strong(totals.1.to_string());
+            ui.strong(totals.2.to_string());
+            ui.end_row();
+        });
+
+    ui.separator();
+    let total: usize = stats.reviews_per_day.iter().map(|(_, count)| count).sum();
+    ui.heading(format!(
+        "Reviews in the last {} days ({})",
+        STATISTICS_DAYS, total
+    ));
+    let bars: Vec<(String, usize)> = stats
+        .reviews_per_day
+        .iter()
+        .map(|(date, count)| (date.format("%Y-%m-%d").to_string(), *count))
+        .collect();
+    bar_chart(ui, &bars);
+
+    ui.separator();
+    ui.heading("Retention by interval");
+    egui::Grid::new("statistics_retention")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Interval");
+            ui.strong("Reviews");
+            ui.strong("Recalled");
+            ui.end_row();
+
+            for bucket in &stats.retention {
+                ui.label(bucket.label);
+                ui.label(bucket.reviews.to_string());
+                ui.label(
+                    bucket
+                        .rate()
+                        .map_or("-".to_string(), |rate| format!("{:.0}%", rate * 100.0)),
+                );
+                ui.end_row();
+            }
+        });
+
+    ui.separator();
+    ui.heading("Easiness factor");
+    match stats.average_easiness {
+        Some(average) => ui.label(format!("Average of reviewed cards: {:.2}", average)),
+        None => ui.label("No cards reviewed yet"),
+    };
+    let bars: Vec<(String, usize)> = stats
+        .easiness
+        .iter()
+        .enumerate()
+        .map(|(i, (lower, count))| {
+            let label = match stats.easiness.get(i + 1) {
+                Some((upper, _)) => format!("{:.1}-{:.1}", lower, upper),
+                None => format!("{:.1}+", lower),
+            };
+            (label, *count)
+        })
+        .collect();
+    bar_chart(ui, &bars);
+
+    ui.separator();
+    let total: usize = stats.forecast.iter().sum();
+    ui.heading(format!(
+        "Due in the next {} days ({})",
+        STATISTICS_DAYS, total
+    ));
+    let bars: Vec<(String, usize)> = stats
+        .forecast
+        .iter()
+        .enumerate()
+        .map(|(day, count)| {
+            let label = match day {
+                0 => "Today".to_string(),
+                1 => "Tomorrow".to_string(),
+                day => format!("In {} days", day),
+            };
+            (label, *count)
+        })
+        .collect();
+    bar_chart(ui, &bars);
+}
@@ -0,0 +1,144 @@
+//! Dialog for studying the cards matching a tag expression.
+//! The expression is checked while it is typed, and the cards can be taken
+//! from any selection of decks.
+
+use super::{AppScreen, MyApp};
+use crate::database::db;
+use crate::models::{LearningSession, TagFilter};
+use eframe::egui;
+use std::sync::Arc;
+
+/// Tag expression and decks chosen for a tag-filtered session
+pub(super) struct TagStudy {
+    expression: String,
+    /// Every deck and whether its cards are included
+    decks: Vec<(String, bool)>,
+    /// Tags in use with their number of cards, shown as a hint
+    tags: Vec<(String, u32)>,
+}
+
+impl MyApp {
+    /// Opens the tag study dialog with all decks selected
+    pub(super) fn open_tag_study(&mut self) {
+        let tags = self.with_db(db::get_all_tags).unwrap_or_default();
+        self.tag_study = Some(TagStudy {
+            expression: String::new(),
+            decks: self
+                .all_decks
+                .decks
+                .iter()
+                .map(|deck| (deck.name.clone(), true))
+                .collect(),
+            tags,
+        });
+    }
+
+    /// Renders the tag study dialog
+    pub(super) fn render_tag_study_dialog(&mut self, ctx: &egui::Context) {
+        let Some(study) = &mut self.tag_study else {
+            return;
+        };
+        let mut start = false;
+        let mut cancel = false;
+        let filter = study.expression.parse::<TagFilter>();
+
+        egui::Window::new("Study by Tags")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Tags:");
+                    ui.text_edit_singleline(&mut study.expression);
+                });
+                ui.label("e.g. lesson1 and (noun or verb) and not irregular");
+                if let Err(e) = &filter {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+
+                if !study.tags.is_empty() {
+                    ui.add_space(5.0);
+                    let tags: Vec<String> = study
+                        .tags
+                        .iter()
+                        .map(|(tag, count)| format!("{} ({})", tag, count))
+                        .collect();
+                    ui.label(format!("Available: {}", tags.join(", ")));
+                }
+
+                ui.separator();
+                ui.label("Decks:");
+                for (name, selected) in &mut study.decks {
+                    ui.checkbox(selected, name.as_str());
+                }
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(filter.is_ok(), egui::Button::new("Start"))
+                        .clicked()
+                    {
+                        start = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if start
+            && let Ok(filter) = filter
+            && let Some(study) = self.tag_study.take()
+        {
+            self.start_tag_session(&study, &filter);
+        }
+        if cancel {
+            self.tag_study = None;
+        }
+    }
+
+    /// Starts a learning session with the due cards of the chosen decks matching the filter
+    fn start_tag_session(&mut self, study: &TagStudy, filter: &TagFilter) {
+        let deck_names: Vec<String> = study
+            .decks
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(name, _)| name.clone())
+            .collect();
+        let Some(cards) =
+            self.with_db(|conn| db::get_flashcards_for_tag_session(&deck_names, filter, conn))
+        else {
+            return;
+        };
+
+        let description = match study.expression.trim() {
+            "" => "all tags".to_string(),
+            expression => expression.to_string(),
+        };
+        if cards.is_empty() {
+            self.show_message(format!(
+                "No cards tagged '{}' left to study today.",
+                description
+            ));
+            return;
+        }
+        let Some(conn) = &self.conn else {
+            return;
+        };
+
+        let mut session = LearningSession::new_from_several_decks(
+            format!("{} ({})", description, deck_names.join(", ")),
+            cards,
+            Arc::clone(conn),
+        );
+        session.mode = self.study_mode;
+        session.choice_pool = self
+            .all_decks
+            .decks
+            .iter()
+            .filter(|deck| deck_names.contains(&deck.name))
+            .flat_map(|deck| deck.flashcards.iter().cloned())
+            .collect();
+        self.learning_session = Some(session);
+        self.current_screen = AppScreen::LearningSession;
+    }
+}
@@ -0,0 +1,102 @@
+//! Playback of card audio clips.
+//! Clips are played by the platform's command-line player, so no audio
+//! libraries are linked: `afplay` on macOS, PowerShell on Windows (WAV only),
+//! and elsewhere the first of `ffplay`, `mpv`, `paplay` and `aplay` installed.
+//! Starting a clip stops the one still playing.
+
+use crate::error::{FlashcardsError, Result};
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+/// Commands that can play the file at `path`, in order of preference
+fn player_commands(path: &Path) -> Vec<Command> {
+    let command = |program: &str, args: &[&str]| {
+        let mut command = Command::new(program);
+        command.args(args).arg(path);
+        command
+    };
+    if cfg!(target_os = "macos") {
+        vec![command("afplay", &[])]
+    } else if cfg!(windows) {
+        let mut command = Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-Command",
+            &format!(
+                "(New-Object Media.SoundPlayer '{}').PlaySync()",
+                path.display().to_string().replace('\'', "''")
+            ),
+        ]);
+        vec![command]
+    } else {
+        vec![
+            command("ffplay", &["-nodisp", "-autoexit", "-loglevel", "quiet"]),
+            command("mpv", &["--no-video", "--really-quiet"]),
+            command("paplay", &[]),
+            command("aplay", &["-q"]),
+        ]
+    }
+}
+
+/// Joins names for a message, e.g. "a, b or c"
+fn or_list(names: &[String]) -> String {
+    match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+/// Plays one clip at a time in the background
+#[derive(Default)]
+pub struct AudioPlayer {
+    playing: Option<Child>,
+}
+
+impl AudioPlayer {
+    /// Starts playing a file, stopping the clip still playing
+    ///
+    /// Fails if no supported player is installed.
+    pub fn play(&mut self, path: &Path) -> Result<()> {
+        self.stop();
+        let commands = player_commands(path);
+        let programs: Vec<String> = commands
+            .iter()
+            .map(|command| command.get_program().to_string_lossy().into_owned())
+            .collect();
+        for mut command in commands {
+            let spawned = command
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            match spawned {
+                Ok(child) => {
+                    self.playing = Some(child);
+                    return Ok(());
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(FlashcardsError::Io(io::Error::new(
+            ErrorKind::NotFound,
+            format!("no audio player found; install {}", or_list(&programs)),
+        )))
+    }
+
+    /// Stops the clip being played, if any
+    pub fn stop(&mut self) {
+        if let Some(mut child) = self.playing.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for AudioPlayer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,192 @@
+//! Command-line arguments.
+//! Running `flashcards` without a subcommand launches the GUI.
+
+use clap::{Args, Parser, Subcommand};
+use flashcards_app::export::merge::ConflictPolicy;
+use flashcards_app::models::{DirectionMode, StudyMode};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "flashcards", version, about = "Spaced repetition flashcards")]
+pub struct Cli {
+    /// Database file to use instead of a profile [env: FLASHCARDS_DB]
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "profile")]
+    pub db: Option<PathBuf>,
+
+    /// Named profile with its own database [env: FLASHCARDS_PROFILE]
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(flatten)]
+    Database(DatabaseCommand),
+    /// Manage profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+}
+
+/// Subcommands working on the database of the selected profile or --db
+#[derive(Subcommand)]
+pub enum DatabaseCommand {
+    /// Manage decks
+    Deck {
+        #[command(subcommand)]
+        command: DeckCommand,
+    },
+    /// Manage flashcards
+    Card {
+        #[command(subcommand)]
+        command: CardCommand,
+    },
+    /// Import a deck from a JSON, CSV, TSV or Anki .apkg file
+    Import {
+        file: PathBuf,
+        /// Name of the deck created from a CSV/TSV file or Anki package
+        /// [default: file name or Anki deck name]
+        #[arg(long)]
+        deck: Option<String>,
+        /// The first row of a CSV/TSV file is a flashcard, not column names
+        #[arg(long)]
+        no_header: bool,
+        /// Start cards from an Anki package as new instead of keeping their progress
+        #[arg(long)]
+        reset_progress: bool,
+        /// Merge into an existing deck, resolving changed cards with
+        /// keep, overwrite, both or ask
+        #[arg(long, value_name = "POLICY")]
+        on_conflict: Option<ConflictPolicy>,
+    },
+    /// Export a deck to a JSON, CSV or TSV file, chosen by the file extension
+    Export { deck: String, file: PathBuf },
+    /// Write all decks, cards, review progress and settings to a JSON file
+    Backup { file: PathBuf },
+    /// Replace the whole database with the contents of a backup file
+    Restore {
+        file: PathBuf,
+        /// Overwrite a database that already contains decks
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show due card counts for every deck, or the due cards of one deck
+    Due { deck: Option<String> },
+    /// Review the due cards of one or more decks in the terminal
+    Study {
+        #[arg(required = true)]
+        decks: Vec<String>,
+        /// self, typed or choice; defaults to the mode chosen in the settings
+        #[arg(long, value_name = "MODE")]
+        mode: Option<StudyMode>,
+        /// Only study cards matching a tag expression, e.g. "lesson1 and not verb"
+        #[arg(long, value_name = "EXPR")]
+        tags: Option<String>,
+    },
+    /// List the tags in use with their number of cards
+    Tags,
+    /// Show card counts per deck, retention by interval and the due forecast
+    Stats,
+    /// Project the daily reviews and new cards of a deck
+    Forecast {
+        deck: String,
+        /// Number of days to project
+        #[arg(long, default_value_t = 30)]
+        days: usize,
+        /// Simulate future reviews recalled with this probability (0-1);
+        /// without it only cards already scheduled are counted
+        #[arg(long, value_name = "P")]
+        recall: Option<f64>,
+        /// Number of cards to be added to the deck
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        add: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommand {
+    /// List existing profiles
+    List,
+    /// Print the database path of the selected profile or --db
+    Path,
+}
+
+#[derive(Subcommand)]
+pub enum DeckCommand {
+    /// List decks with their card and due counts
+    List,
+    /// Create an empty deck
+    Create { name: String },
+    /// Rename a deck, keeping its cards and review progress
+    Rename { name: String, new_name: String },
+    /// Set the directions studied: forward, reverse or both
+    Directions { name: String, mode: DirectionMode },
+    /// Show or change the number of new cards and reviews studied per day
+    Limits {
+        name: String,
+        #[arg(long = "new", value_name = "N")]
+        new_cards: Option<u32>,
+        #[arg(long, value_name = "N")]
+        reviews: Option<u32>,
+    },
+    /// Delete a deck with all of its cards and review progress
+    Delete { name: String },
+}
+
+#[derive(Subcommand)]
+pub enum CardCommand {
+    /// Add a flashcard to a deck and print its ID
+    Add {
+        deck: String,
+        term: String,
+        definition: String,
+        /// Tags separated by spaces or commas
+        #[arg(long)]
+        tags: Option<String>,
+        #[command(flatten)]
+        media: MediaArgs,
+    },
+    /// List the flashcards of a deck
+    List { deck: String },
+    /// Change the term, definition, directions, tags and/or media of a flashcard
+    Edit {
+        id: i64,
+        #[arg(long)]
+        term: Option<String>,
+        #[arg(long)]
+        definition: Option<String>,
+        /// forward, reverse, both, or deck to follow the deck setting
+        #[arg(long, value_name = "MODE")]
+        directions: Option<String>,
+        /// Replace the tags, separated by spaces or commas ("" removes all)
+        #[arg(long)]
+        tags: Option<String>,
+        #[command(flatten)]
+        media: MediaArgs,
+    },
+    /// Move a flashcard to another deck, keeping its review progress
+    Move { id: i64, deck: String },
+    /// Delete a flashcard with its review progress
+    Delete { id: i64 },
+}
+
+/// Media files of a flashcard; an empty file name ("") removes one
+#[derive(Args)]
+pub struct MediaArgs {
+    /// Image file shown with the term
+    #[arg(long, value_name = "FILE")]
+    pub term_image: Option<String>,
+    /// Image file shown with the definition
+    #[arg(long, value_name = "FILE")]
+    pub definition_image: Option<String>,
+    /// Audio file played with the term
+    #[arg(long, value_name = "FILE")]
+    pub term_audio: Option<String>,
+    /// Audio file played with the definition
+    #[arg(long, value_name = "FILE")]
+    pub definition_audio: Option<String>,
+}
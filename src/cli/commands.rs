@@ -0,0 +1,478 @@
+//! Execution of command-line subcommands.
+//! Output is tab-separated so it can be consumed by shell scripts; confirmations
+//! meant for people go to stderr.
+
+use super::args::{CardCommand, Command, DatabaseCommand, DeckCommand, MediaArgs, ProfileCommand};
+use super::study::study;
+use chrono::{DateTime, Local};
+use flashcards_app::database::db;
+use flashcards_app::database::location::{
+    self, DEFAULT_PROFILE, DatabaseLocation, LEGACY_DATABASE_FILE,
+};
+use flashcards_app::database::media::{MediaFile, MediaKind, MediaStore};
+use flashcards_app::error::{FlashcardsError, Result};
+use flashcards_app::export::anki::{import_apkg_package, read_apkg};
+use flashcards_app::export::backup::{backup_to_path, read_backup, restore_backup};
+use flashcards_app::export::csv::{
+    CsvOptions, export_csv_to_path, import_csv, is_delimited_text, preview_csv,
+};
+use flashcards_app::export::json::{export_json_to_path, import_json};
+use flashcards_app::export::merge::{Conflict, ConflictPolicy, Resolution, merge_deck};
+use flashcards_app::models::simulator::SimulationOptions;
+use flashcards_app::models::tag_filter::split_tags;
+use flashcards_app::models::{Deck, DeckNode, Statistics};
+use rusqlite::Connection;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Opens the database selected by flags and environment
+///
+/// The first time the default profile is used, a database from an older version
+/// left in the working directory is copied to it so no decks are lost.
+pub fn open_database(location: &DatabaseLocation) -> Result<Connection> {
+    let path = location.path()?;
+
+    if location.profile() == Some(DEFAULT_PROFILE)
+        && location::adopt_legacy_database(Path::new(LEGACY_DATABASE_FILE), &path)?
+    {
+        eprintln!(
+            "Copied '{}' from the current directory to '{}'.",
+            LEGACY_DATABASE_FILE,
+            path.display()
+        );
+    }
+
+    db::init_database(&path)
+}
+
+/// Runs a subcommand; profile commands work without opening the database
+pub fn run(command: Command, location: &DatabaseLocation) -> Result<()> {
+    match command {
+        Command::Database(command) => run_database(command, open_database(location)?),
+        Command::Profile { command } => run_profile(command, location),
+    }
+}
+
+/// Runs a subcommand against the selected database
+fn run_database(command: DatabaseCommand, conn: Connection) -> Result<()> {
+    match command {
+        DatabaseCommand::Deck { command } => run_deck(command, &conn),
+        DatabaseCommand::Card { command } => run_card(command, &conn),
+        DatabaseCommand::Import {
+            file,
+            deck,
+            no_header,
+            reset_progress,
+            on_conflict,
+        } => run_import(&file, deck, no_header, reset_progress, on_conflict, &conn),
+        DatabaseCommand::Export { deck, file } => {
+            db::ensure_deck_exists(&deck, &conn)?;
+            let flashcards = db::get_flashcards_for_deck(&deck, &conn)?
+                .into_iter()
+                .map(|(_, fc)| fc)
+                .collect();
+            let deck = Deck {
+                name: deck,
+                flashcards,
+            };
+            if is_delimited_text(&file) {
+                export_csv_to_path(&deck, &file, &CsvOptions::for_path(&file))?;
+            } else {
+                let media = MediaStore::for_connection(&conn);
+                export_json_to_path(&deck, &file.to_string_lossy(), &media)?;
+            }
+            eprintln!("Deck '{}' exported to '{}'", deck.name, file.display());
+            Ok(())
+        }
+        DatabaseCommand::Backup { file } => {
+            let backup = backup_to_path(&conn, &file)?;
+            println!(
+                "{} decks, {} cards, {} reviews",
+                backup.decks.len(),
+                backup.flashcards.len(),
+                backup.review_log.len()
+            );
+            Ok(())
+        }
+        DatabaseCommand::Restore { file, force } => {
+            let backup = read_backup(&file)?;
+            if !force && !db::get_all_decks(&conn)?.is_empty() {
+                return Err(FlashcardsError::Validation(
+                    "The database already contains decks; use --force to replace them".to_string(),
+                ));
+            }
+            restore_backup(&backup, &conn)?;
+            println!(
+                "{} decks, {} cards, {} reviews",
+                backup.decks.len(),
+                backup.flashcards.len(),
+                backup.review_log.len()
+            );
+            Ok(())
+        }
+        DatabaseCommand::Due { deck: Some(deck) } => {
+            db::ensure_deck_exists(&deck, &conn)?;
+            for (id, flashcard, review) in db::get_flashcards_due_for_review(&deck, &conn)? {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    id,
+                    flashcard.term,
+                    flashcard.definition,
+                    format_system_time(review.next_review_date)
+                );
+            }
+            Ok(())
+        }
+        DatabaseCommand::Study { decks, mode, tags } => {
+            for deck in &decks {
+                db::ensure_deck_exists(deck, &conn)?;
+            }
+            let mode = match mode {
+                Some(mode) => mode,
+                None => db::get_study_mode(&conn)?,
+            };
+            study(&decks, tags.as_deref(), mode, conn)
+        }
+        DatabaseCommand::Tags => {
+            for (tag, count) in db::get_all_tags(&conn)? {
+                println!("{}\t{}", tag, count);
+            }
+            Ok(())
+        }
+        DatabaseCommand::Stats => {
+            print_statistics(&db::get_statistics(&conn)?);
+            Ok(())
+        }
+        DatabaseCommand::Forecast {
+            deck,
+            days,
+            recall,
+            add,
+        } => {
+            let options = SimulationOptions {
+                days,
+                recall_probability: recall,
+                added_cards: add,
+                ..SimulationOptions::default()
+            };
+            let forecast = db::get_workload_forecast(&deck, &options, &conn)?;
+            println!("date\treviews\tnew\tbacklog");
+            for day in forecast {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    day.date, day.reviews, day.new_cards, day.backlog
+                );
+            }
+            Ok(())
+        }
+        DatabaseCommand::Due { deck: None } => {
+            for deck in db::get_all_decks(&conn)? {
+                let due = db::get_flashcards_due_for_review(&deck, &conn)?.len();
+                println!("{}\t{}", deck, due);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn run_import(
+    file: &Path,
+    deck_name: Option<String>,
+    no_header: bool,
+    reset_progress: bool,
+    on_conflict: Option<ConflictPolicy>,
+    conn: &Connection,
+) -> Result<()> {
+    let file_stem = || {
+        file.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+
+    if file
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("apkg"))
+    {
+        let package = read_apkg(file)?;
+        let name = deck_name.unwrap_or_else(|| package.deck_name.clone());
+        import_apkg_package(&package, &name, !reset_progress, conn)?;
+        for omission in package.omissions() {
+            eprintln!("{}", omission);
+        }
+        println!("{}\t{} cards", name, package.cards.len());
+        return Ok(());
+    }
+
+    let deck = if is_delimited_text(file) {
+        let mut options = CsvOptions {
+            has_header: !no_header,
+            ..CsvOptions::for_path(file)
+        };
+        options.columns.tags = preview_csv(File::open(file)?, &options, 0)?.tags_column();
+        import_csv(file, &deck_name.unwrap_or_else(file_stem), &options)?
+    } else {
+        import_json(&file.to_string_lossy(), &MediaStore::for_connection(conn))?
+    };
+
+    if let Some(policy) = on_conflict {
+        let report = merge_deck(&deck, &deck.name, policy, ask_resolution, conn)?;
+        println!("{}\t{}", deck.name, report);
+        return Ok(());
+    }
+    db::import_deck(&deck, conn)?;
+    println!("{}\t{} cards", deck.name, deck.flashcards.len());
+    Ok(())
+}
+
+/// Asks on the terminal how to resolve a conflicting card
+fn ask_resolution(conflict: &Conflict) -> Resolution {
+    println!("{}", conflict.term);
+    println!("  existing: {}", conflict.existing_definition);
+    println!("  imported: {}", conflict.imported_definition);
+
+    loop {
+        print!("[k]eep existing, [o]verwrite, keep [b]oth? ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            // End of input keeps the existing card
+            return Resolution::KeepExisting;
+        }
+        match line.trim() {
+            "k" => return Resolution::KeepExisting,
+            "o" => return Resolution::Overwrite,
+            "b" => return Resolution::KeepBoth,
+            _ => {}
+        }
+    }
+}
+
+fn run_profile(command: ProfileCommand, location: &DatabaseLocation) -> Result<()> {
+    match command {
+        ProfileCommand::List => {
+            for profile in location::list_profiles() {
+                let marker = if location.profile() == Some(profile.as_str()) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{} {}", marker, profile);
+            }
+        }
+        ProfileCommand::Path => println!("{}", location.path()?.display()),
+    }
+    Ok(())
+}
+
+fn run_deck(command: DeckCommand, conn: &Connection) -> Result<()> {
+    match command {
+        DeckCommand::List => {
+            let decks = db::load_all_decks(conn)?;
+            let tree = DeckNode::build(&decks, &db::get_due_counts(conn)?);
+            print_deck_nodes(&tree);
+        }
+        DeckCommand::Create { name } => {
+            db::new_deck(&name, conn)?;
+            eprintln!("Deck '{}' created.", name);
+        }
+        DeckCommand::Rename { name, new_name } => {
+            db::rename_deck(&name, &new_name, conn)?;
+            eprintln!("Deck '{}' renamed to '{}'.", name, new_name);
+        }
+        DeckCommand::Directions { name, mode } => {
+            db::set_deck_directions(&name, mode, conn)?;
+        }
+        DeckCommand::Limits {
+            name,
+            new_cards,
+            reviews,
+        } => {
+            let mut limits = db::get_deck_limits(&name, conn)?;
+            if new_cards.is_none() && reviews.is_none() {
+                let studied = db::get_studied_today(&name, conn)?;
+                println!("new\t{}/{}", studied.new_cards, limits.new_cards);
+                println!("reviews\t{}/{}", studied.reviews, limits.reviews);
+            } else {
+                limits.new_cards = new_cards.unwrap_or(limits.new_cards);
+                limits.reviews = reviews.unwrap_or(limits.reviews);
+                db::set_deck_limits(&name, &limits, conn)?;
+            }
+        }
+        DeckCommand::Delete { name } => {
+            db::delete_deck(&name, conn)?;
+            eprintln!("Deck '{}' deleted.", name);
+        }
+    }
+    Ok(())
+}
+
+fn run_card(command: CardCommand, conn: &Connection) -> Result<()> {
+    match command {
+        CardCommand::Add {
+            deck,
+            term,
+            definition,
+            tags,
+            media,
+        } => {
+            let tx = conn.unchecked_transaction()?;
+            let id = db::add_flashcard(&deck, &term, &definition, &tx)?;
+            if let Some(tags) = tags {
+                db::set_flashcard_tags(id, &split_tags(&tags), &tx)?;
+            }
+            let files = set_media(id, media, &tx)?;
+            tx.commit()?;
+            store_media(&files, conn)?;
+            println!("{}", id);
+        }
+        CardCommand::List { deck } => {
+            db::ensure_deck_exists(&deck, conn)?;
+            for (id, flashcard) in db::get_flashcards_for_deck(&deck, conn)? {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    id,
+                    flashcard.term,
+                    flashcard.definition,
+                    flashcard.tags.join(" ")
+                );
+            }
+        }
+        CardCommand::Edit {
+            id,
+            term,
+            definition,
+            directions,
+            tags,
+            media,
+        } => {
+            let tx = conn.unchecked_transaction()?;
+            let (_, current) = db::get_flashcard(id, &tx)?;
+            let term = term.unwrap_or(current.term);
+            let definition = definition.unwrap_or(current.definition);
+            db::update_flashcard(id, &term, &definition, &tx)?;
+            if let Some(directions) = directions {
+                let mode = match directions.as_str() {
+                    "deck" => None,
+                    other => Some(other.parse().map_err(FlashcardsError::Validation)?),
+                };
+                db::set_flashcard_directions(id, mode, &tx)?;
+            }
+            if let Some(tags) = tags {
+                db::set_flashcard_tags(id, &split_tags(&tags), &tx)?;
+            }
+            let files = set_media(id, media, &tx)?;
+            tx.commit()?;
+            store_media(&files, conn)?;
+        }
+        CardCommand::Move { id, deck } => {
+            db::move_flashcard(id, &deck, conn)?;
+        }
+        CardCommand::Delete { id } => {
+            db::delete_flashcard(id, conn)?;
+        }
+    }
+    Ok(())
+}
+
+/// Changes the media of a flashcard given by `--*-image` and `--*-audio` options
+///
+/// Media without an option are kept. The new files are only read and returned,
+/// to be copied into the media store with `store_media` once the card is saved,
+/// so a card that fails to save leaves no unused files behind.
+fn set_media(flashcard_id: i64, args: MediaArgs, conn: &Connection) -> Result<Vec<MediaFile>> {
+    let (_, current) = db::get_flashcard(flashcard_id, conn)?;
+    let mut files = Vec::new();
+    let mut stored = |file: Option<String>, current: Option<String>, kind| match file.as_deref() {
+        None => Ok(current),
+        Some("") => Ok(None),
+        Some(file) => {
+            let file = MediaStore::read_file(Path::new(file), kind)?;
+            let name = file.name.clone();
+            files.push(file);
+            Ok::<_, FlashcardsError>(Some(name))
+        }
+    };
+    let term_image = stored(args.term_image, current.term_image, MediaKind::Image)?;
+    let definition_image = stored(
+        args.definition_image,
+        current.definition_image,
+        MediaKind::Image,
+    )?;
+    let term_audio = stored(args.term_audio, current.term_audio, MediaKind::Audio)?;
+    let definition_audio = stored(
+        args.definition_audio,
+        current.definition_audio,
+        MediaKind::Audio,
+    )?;
+    db::set_flashcard_images(
+        flashcard_id,
+        term_image.as_deref(),
+        definition_image.as_deref(),
+        conn,
+    )?;
+    db::set_flashcard_audio(
+        flashcard_id,
+        term_audio.as_deref(),
+        definition_audio.as_deref(),
+        conn,
+    )?;
+    Ok(files)
+}
+
+/// Copies the files returned by `set_media` into the media store
+fn store_media(files: &[MediaFile], conn: &Connection) -> Result<()> {
+    let media = MediaStore::for_connection(conn);
+    for file in files {
+        media.add(file)?;
+    }
+    Ok(())
+}
+
+/// Formats SystemTime as YYYY-MM-DD HH:MM string
+fn format_system_time(time: SystemTime) -> String {
+    let datetime: DateTime<Local> = time.into();
+    datetime.format("%Y-%m-%d %H:%M").to_string()
+}
+
+/// Prints every deck of the tree with the card and due counts of its whole subtree
+fn print_deck_nodes(nodes: &[DeckNode]) {
+    for node in nodes {
+        println!("{}\t{}\t{}", node.name, node.card_count, node.due_count);
+        print_deck_nodes(&node.children);
+    }
+}
+
+/// Prints the statistics as tab-separated sections
+fn print_statistics(stats: &Statistics) {
+    println!("deck\tnew\tlearning\tmature");
+    for deck in &stats.decks {
+        println!(
+            "{}\t{}\t{}\t{}",
+            deck.deck_name, deck.new_cards, deck.learning, deck.mature
+        );
+    }
+
+    println!("\ninterval\treviews\trecalled");
+    for bucket in &stats.retention {
+        let rate = bucket
+            .rate()
+            .map_or("-".to_string(), |rate| format!("{:.0}%", rate * 100.0));
+        println!("{}\t{}\t{}", bucket.label, bucket.reviews, rate);
+    }
+
+    if let Some(average) = stats.average_easiness {
+        println!("\naverage easiness factor\t{:.2}", average);
+    }
+
+    println!("\ndate\treviews");
+    for (date, count) in &stats.reviews_per_day {
+        println!("{}\t{}", date, count);
+    }
+
+    println!("\nin days\tdue");
+    for (day, due) in stats.forecast.iter().enumerate() {
+        println!("{}\t{}", day, due);
+    }
+}
@@ -0,0 +1,6 @@
+mod args;
+mod commands;
+mod study;
+
+pub use args::Cli;
+pub use commands::{open_database, run};
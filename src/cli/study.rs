@@ -0,0 +1,229 @@
+//! Text-mode learning session.
+//! Drives `LearningSession` exactly like the GUI learning screen does.
+//! With auto-play enabled in the settings, the answer's audio plays on reveal.
+
+use flashcards_app::audio::AudioPlayer;
+use flashcards_app::database::db;
+use flashcards_app::database::media::MediaStore;
+use flashcards_app::error::{FlashcardsError, Result};
+use flashcards_app::models::answer::DiffKind;
+use flashcards_app::models::markdown::to_plain_text;
+use flashcards_app::models::{AnswerCheck, LearningSession, StudyMode, TagFilter};
+use rusqlite::Connection;
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+/// User input while a card is shown
+enum Input {
+    Line(String),
+    Quit,
+}
+
+fn prompt(message: &str) -> io::Result<Input> {
+    print!("{}", message);
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line)? == 0 {
+        // End of input behaves like quitting
+        return Ok(Input::Quit);
+    }
+
+    let line = line.trim();
+    if line.eq_ignore_ascii_case("q") {
+        Ok(Input::Quit)
+    } else {
+        Ok(Input::Line(line.to_string()))
+    }
+}
+
+/// Reviews the due cards of one or more decks in the terminal, optionally
+/// only those matching a tag expression
+pub fn study(
+    deck_names: &[String],
+    tags: Option<&str>,
+    mode: StudyMode,
+    conn: Connection,
+) -> Result<()> {
+    let filter: TagFilter = tags
+        .unwrap_or_default()
+        .parse()
+        .map_err(FlashcardsError::Validation)?;
+    let mut name = deck_names.join(", ");
+    if let Some(tags) = tags {
+        name = format!("{} [{}]", name, tags);
+    }
+
+    // Studying a deck includes its sub-decks
+    let mut decks: Vec<String> = Vec::new();
+    for deck_name in deck_names {
+        for deck in db::get_subtree_decks(deck_name, &conn)? {
+            if !decks.contains(&deck) {
+                decks.push(deck);
+            }
+        }
+    }
+
+    let due_cards = db::get_flashcards_for_tag_session(&decks, &filter, &conn)?;
+    if due_cards.is_empty() {
+        println!("No cards left to study in '{}' today.", name);
+        return Ok(());
+    }
+    let answer_options = db::get_answer_options(&conn)?;
+    let media = MediaStore::for_connection(&conn);
+    let mut player = db::get_auto_play_audio(&conn)?.then(AudioPlayer::default);
+    let mut choice_pool = Vec::new();
+    for deck_name in &decks {
+        choice_pool.extend(
+            db::get_flashcards_for_deck(deck_name, &conn)?
+                .into_iter()
+                .map(|(_, flashcard)| flashcard),
+        );
+    }
+
+    let mut session =
+        LearningSession::new_from_several_decks(name, due_cards, Arc::new(Mutex::new(conn)));
+    session.mode = mode;
+    session.choice_pool = choice_pool;
+
+    println!("Learning: {} (q to quit)", session.deck_name);
+    let mut last_round = 0;
+
+    while !session.is_completed() {
+        if session.round_number != last_round {
+            last_round = session.round_number;
+            println!();
+            println!("{}", session.phase_message());
+        }
+
+        let Some(card) = session.current_card() else {
+            break;
+        };
+        let prompt_label = card.prompt_label();
+        let answer_label = card.answer_label();
+        // The terminal shows card faces without their Markdown formatting
+        let question = to_plain_text(card.prompt());
+        let answer = to_plain_text(card.answer());
+        let answer_audio = card.answer_audio().map(str::to_string);
+
+        println!();
+        println!(
+            "Progress: {} / {} learned ({} remaining)",
+            session.learned_count(),
+            session.total_count(),
+            session.remaining_count()
+        );
+        println!("{}: {}", prompt_label, question);
+
+        if session.mode == StudyMode::MultipleChoice {
+            let Some(quality) = ask_choice(&mut session)? else {
+                return Ok(());
+            };
+            println!("{}: {}", answer_label, answer);
+            play_audio(player.as_mut(), &media, answer_audio.as_deref());
+            session.grade_current_card(quality)?;
+            session.next_card();
+            continue;
+        }
+
+        let message = match session.mode {
+            StudyMode::Typed => format!("{}: ", answer_label),
+            _ => format!("Press Enter to show the {}...", answer_label.to_lowercase()),
+        };
+        let Input::Line(typed) = prompt(&message)? else {
+            return Ok(());
+        };
+        let suggested = if session.mode == StudyMode::Typed {
+            session.typed_answer = typed;
+            session.check_typed_answer(&answer_options).map(|check| {
+                println!("Diff: {}", format_diff(check));
+                check.suggested_quality
+            })
+        } else {
+            session.toggle_definition();
+            None
+        };
+        println!("{}: {}", answer_label, answer);
+        play_audio(player.as_mut(), &media, answer_audio.as_deref());
+
+        let quality = loop {
+            let message = match suggested {
+                Some(quality) => format!("Grade [Enter accepts the suggested {}]: ", quality),
+                None => "Rate your response (0 blackout, 1 wrong, 2 wrong (familiar), \
+                         3 difficult, 4 correct, 5 perfect): "
+                    .to_string(),
+            };
+            match (prompt(&message)?, suggested) {
+                (Input::Quit, _) => return Ok(()),
+                (Input::Line(line), Some(quality)) if line.is_empty() => break quality,
+                (Input::Line(line), _) => match line.parse::<u8>() {
+                    Ok(quality) if quality <= 5 => break quality,
+                    _ => println!("Please enter a number from 0 to 5."),
+                },
+            }
+        };
+
+        session.grade_current_card(quality)?;
+        session.next_card();
+    }
+
+    println!();
+    println!("Congratulations! You've learned all due cards in this deck!");
+    Ok(())
+}
+
+/// Plays a card's audio clip if there is a player; failures are only reported
+fn play_audio(player: Option<&mut AudioPlayer>, media: &MediaStore, name: Option<&str>) {
+    let (Some(player), Some(name)) = (player, name) else {
+        return;
+    };
+    if let Err(e) = media.path(name).and_then(|path| player.play(&path)) {
+        eprintln!("Failed to play audio: {}", e);
+    }
+}
+
+/// Lists the choices for the current card and reads the pick
+///
+/// Returns the quality grade of the pick, or None if the learner quits.
+fn ask_choice(session: &mut LearningSession) -> io::Result<Option<u8>> {
+    let Some(question) = session.current_choices().cloned() else {
+        return Ok(None);
+    };
+    for (i, choice) in question.choices.iter().enumerate() {
+        println!("  {}) {}", i + 1, choice);
+    }
+
+    loop {
+        match prompt("Choice: ")? {
+            Input::Quit => return Ok(None),
+            Input::Line(line) => match line.parse::<usize>() {
+                Ok(n) if (1..=question.choices.len()).contains(&n) => {
+                    if n - 1 == question.correct_index {
+                        println!("Correct!");
+                    } else {
+                        println!("Wrong.");
+                    }
+                    return Ok(session.choose(n - 1));
+                }
+                _ => println!(
+                    "Please enter a number from 1 to {}.",
+                    question.choices.len()
+                ),
+            },
+        }
+    }
+}
+
+/// Marks the difference between typed and expected answer with brackets:
+/// `[+x]` for missing and `[-x]` for wrongly typed characters
+fn format_diff(check: &AnswerCheck) -> String {
+    check
+        .diff
+        .iter()
+        .map(|segment| match segment.kind {
+            DiffKind::Same => segment.text.clone(),
+            DiffKind::Missing => format!("[+{}]", segment.text),
+            DiffKind::Extra => format!("[-{}]", segment.text),
+        })
+        .collect()
+}
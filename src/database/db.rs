@@ -1,122 +1,687 @@
 //! Database operations for flashcard application
 //!
 //! Handles SQLite database initialization, CRUD operations for decks and flashcards,
-//! and SM-2 spaced repetition data management.
+//! and spaced repetition data management.
 
-use crate::models::{Deck, DeckSet, Flashcard, ReviewData};
-use rusqlite::{Connection, Result, params};
+use super::media::validate_media_name;
+use super::migrations;
+use crate::error::{FlashcardsError, Result};
+use crate::models::clock::{due_cutoff, today_start};
+use crate::models::deck_tree::{ancestor_names, is_in_subtree, validate_deck_name};
+use crate::models::limits::apply_limits;
+use crate::models::simulator::{DayWorkload, SimulationOptions, simulate_workload};
+use crate::models::tag_filter::validate_tag;
+use crate::models::{
+    AnswerOptions, CardDirection, ClockMode, DEFAULT_ROLLOVER_HOUR, DailyLimits, Deck, DeckSet,
+    DirectionMode, Flashcard, ReviewData, ReviewLogEntry, SchedulerKind, Statistics, StudiedToday,
+    StudyMode, TagFilter,
+};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::time::{Duration, SystemTime};
 
-/// Initializes SQLite database with required tables
+/// Opens the SQLite database at `path` and migrates it to the current schema
 ///
-/// Creates tables for decks, flashcards, SM-2 review data, and app state.
-/// Sets current date to now if not already initialized.
-pub fn init_database() -> Result<Connection> {
-    let conn = Connection::open("db.sqlite3")?;
+/// Creates the parent directory if needed, and tables for decks, flashcards,
+/// scheduling review data, review history, and app state.
+/// Fails if the database was created by a newer version of the app.
+pub fn init_database(path: &Path) -> Result<Connection> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
 
-    // Create decks table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS decks (
-            name TEXT PRIMARY KEY
-        )",
-        (),
-    )?;
+    let mut conn = Connection::open(path)?;
+    prepare_connection(&mut conn)?;
+    Ok(conn)
+}
 
-    // Create flashcards table with auto-increment ID
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS flashcards (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            deck_name TEXT NOT NULL,
-            term TEXT NOT NULL,
-            definition TEXT NOT NULL,
-            FOREIGN KEY (deck_name) REFERENCES decks(name),
-            UNIQUE(deck_name, term)
-        )",
-        (),
-    )?;
+/// Opens a temporary in-memory database with the current schema
+pub fn init_in_memory_database() -> Result<Connection> {
+    let mut conn = Connection::open_in_memory()?;
+    prepare_connection(&mut conn)?;
+    Ok(conn)
+}
+
+/// Migrates the schema and sets the simulated date to now if not already initialized
+fn prepare_connection(conn: &mut Connection) -> Result<()> {
+    migrations::migrate(conn)?;
+
+    // Initialize current_date if not exists
+    let current_timestamp = to_timestamp(SystemTime::now());
 
-    // Create review_data table for SM-2 algorithm
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS review_data (
-            flashcard_id INTEGER PRIMARY KEY,
-            easiness_factor REAL NOT NULL DEFAULT 2.5,
-            interval_days INTEGER NOT NULL DEFAULT 0,
-            repetitions INTEGER NOT NULL DEFAULT 0,
-            next_review_date INTEGER NOT NULL,
-            FOREIGN KEY (flashcard_id) REFERENCES flashcards(id) ON DELETE CASCADE
-        )",
-        (),
+        "INSERT OR IGNORE INTO app_state (key, value) VALUES ('current_date', ?1)",
+        params![current_timestamp.to_string()],
     )?;
 
-    // Create app_state table for storing current date
+    Ok(())
+}
+
+/// Converts a date to seconds since the Unix epoch as stored in the database
+fn to_timestamp(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Converts seconds since the Unix epoch back to a date
+fn from_timestamp(timestamp: i64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp.max(0) as u64)
+}
+
+/// Reads a value from the app_state table
+fn get_app_state(key: &str, conn: &Connection) -> Result<Option<String>> {
+    let value = conn
+        .query_row(
+            "SELECT value FROM app_state WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(value)
+}
+
+/// Writes a value to the app_state table
+fn set_app_state(key: &str, value: &str, conn: &Connection) -> Result<()> {
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS app_state (
-            key TEXT PRIMARY KEY,
-            value TEXT NOT NULL
-        )",
-        (),
+        "INSERT OR REPLACE INTO app_state (key, value) VALUES (?1, ?2)",
+        params![key, value],
     )?;
+    Ok(())
+}
 
-    // Initialize current_date if not exists
-    let current_timestamp = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+/// Returns where the current date comes from
+pub fn get_clock_mode(conn: &Connection) -> Result<ClockMode> {
+    match get_app_state("clock_mode", conn)? {
+        Some(mode) => mode.parse().map_err(FlashcardsError::Parse),
+        None => Ok(ClockMode::default()),
+    }
+}
 
-    conn.execute(
-        "INSERT OR IGNORE INTO app_state (key, value) VALUES ('current_date', ?1)",
-        params![current_timestamp.to_string()],
+/// Switches between system time and the simulated date
+///
+/// The simulated date never starts behind the real time, so reviews made with
+/// the system clock do not end up in the future.
+pub fn set_clock_mode(mode: ClockMode, conn: &Connection) -> Result<()> {
+    if mode == ClockMode::Simulated && get_simulated_date(conn)? < SystemTime::now() {
+        set_app_state(
+            "current_date",
+            &to_timestamp(SystemTime::now()).to_string(),
+            conn,
+        )?;
+    }
+    set_app_state("clock_mode", mode.as_str(), conn)
+}
+
+/// Returns the hour of local time at which a new learning day starts
+pub fn get_rollover_hour(conn: &Connection) -> Result<u32> {
+    match get_app_state("rollover_hour", conn)? {
+        Some(hour) => hour
+            .parse::<u32>()
+            .ok()
+            .filter(|hour| *hour < 24)
+            .ok_or_else(|| FlashcardsError::Parse(format!("Invalid rollover hour '{}'", hour))),
+        None => Ok(DEFAULT_ROLLOVER_HOUR),
+    }
+}
+
+/// Changes the hour of local time at which a new learning day starts
+pub fn set_rollover_hour(hour: u32, conn: &Connection) -> Result<()> {
+    if hour >= 24 {
+        return Err(FlashcardsError::Validation(format!(
+            "Rollover hour must be between 0 and 23, got {}",
+            hour
+        )));
+    }
+    set_app_state("rollover_hour", &hour.to_string(), conn)
+}
+
+/// Returns how the answer is recalled when learning
+pub fn get_study_mode(conn: &Connection) -> Result<StudyMode> {
+    match get_app_state("study_mode", conn)? {
+        Some(mode) => mode.parse().map_err(FlashcardsError::Parse),
+        None => Ok(StudyMode::default()),
+    }
+}
+
+/// Changes how the answer is recalled when learning
+pub fn set_study_mode(mode: StudyMode, conn: &Connection) -> Result<()> {
+    set_app_state("study_mode", mode.as_str(), conn)
+}
+
+/// Returns how strictly typed answers are checked
+pub fn get_answer_options(conn: &Connection) -> Result<AnswerOptions> {
+    let defaults = AnswerOptions::default();
+    let flag = |key: &str, default: bool| -> Result<bool> {
+        Ok(get_app_state(key, conn)?.map_or(default, |value| value == "1"))
+    };
+    let typo_tolerance = match get_app_state("answer_typo_tolerance", conn)? {
+        Some(value) => value
+            .parse()
+            .map_err(|_| FlashcardsError::Parse(format!("Invalid typo tolerance '{}'", value)))?,
+        None => defaults.typo_tolerance,
+    };
+    Ok(AnswerOptions {
+        ignore_case: flag("answer_ignore_case", defaults.ignore_case)?,
+        ignore_diacritics: flag("answer_ignore_diacritics", defaults.ignore_diacritics)?,
+        typo_tolerance,
+    })
+}
+
+/// Changes how strictly typed answers are checked
+pub fn set_answer_options(options: &AnswerOptions, conn: &Connection) -> Result<()> {
+    let flag = |value: bool| if value { "1" } else { "0" };
+    set_app_state("answer_ignore_case", flag(options.ignore_case), conn)?;
+    set_app_state(
+        "answer_ignore_diacritics",
+        flag(options.ignore_diacritics),
+        conn,
     )?;
+    set_app_state(
+        "answer_typo_tolerance",
+        &options.typo_tolerance.to_string(),
+        conn,
+    )
+}
 
-    Ok(conn)
+/// Returns whether the audio of the answer is played when it is revealed
+pub fn get_auto_play_audio(conn: &Connection) -> Result<bool> {
+    Ok(get_app_state("auto_play_audio", conn)?.is_some_and(|value| value == "1"))
+}
+
+/// Changes whether the audio of the answer is played when it is revealed
+pub fn set_auto_play_audio(enabled: bool, conn: &Connection) -> Result<()> {
+    set_app_state("auto_play_audio", if enabled { "1" } else { "0" }, conn)
 }
 
-/// Retrieves current simulated date from database
+/// Retrieves the current date according to the clock mode
 pub fn get_current_date(conn: &Connection) -> Result<SystemTime> {
-    let timestamp: String = conn.query_row(
-        "SELECT value FROM app_state WHERE key = 'current_date'",
-        [],
-        |row| row.get(0),
-    )?;
+    match get_clock_mode(conn)? {
+        ClockMode::System => Ok(SystemTime::now()),
+        ClockMode::Simulated => get_simulated_date(conn),
+    }
+}
+
+/// Retrieves the simulated date from database
+fn get_simulated_date(conn: &Connection) -> Result<SystemTime> {
+    let timestamp = get_app_state("current_date", conn)?
+        .ok_or_else(|| FlashcardsError::NotFound("Simulated date".to_string()))?;
 
-    let secs = timestamp.parse::<u64>().unwrap_or(0);
-    Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+    let secs = timestamp
+        .parse::<i64>()
+        .map_err(|_| FlashcardsError::Parse(format!("Invalid current date '{}'", timestamp)))?;
+    Ok(from_timestamp(secs))
 }
 
-/// Advances current date by 24 hours (for testing spaced repetition)
+/// Advances the simulated date by 24 hours (for testing spaced repetition)
 pub fn advance_day(conn: &Connection) -> Result<()> {
-    let current = get_current_date(conn)?;
-    let next_day = current + Duration::from_secs(24 * 60 * 60);
-    let timestamp = next_day
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    if get_clock_mode(conn)? != ClockMode::Simulated {
+        return Err(FlashcardsError::Validation(
+            "Days can only be advanced with the simulated clock".to_string(),
+        ));
+    }
 
-    conn.execute(
-        "UPDATE app_state SET value = ?1 WHERE key = 'current_date'",
-        params![timestamp.to_string()],
-    )?;
+    let next_day = get_simulated_date(conn)? + Duration::from_secs(24 * 60 * 60);
+    set_app_state("current_date", &to_timestamp(next_day).to_string(), conn)
+}
 
-    Ok(())
+/// Returns true if a deck with the given name exists
+pub fn deck_exists(name: &str, conn: &Connection) -> Result<bool> {
+    let found = conn
+        .query_row("SELECT 1 FROM decks WHERE name = ?1", params![name], |_| {
+            Ok(())
+        })
+        .optional()?;
+    Ok(found.is_some())
+}
+
+/// Fails with `NotFound` unless the deck exists
+pub fn ensure_deck_exists(name: &str, conn: &Connection) -> Result<()> {
+    if deck_exists(name, conn)? {
+        Ok(())
+    } else {
+        Err(FlashcardsError::NotFound(format!("Deck '{}'", name)))
+    }
 }
 
 /// Creates a new deck in the database
+///
+/// Parent decks missing from a sub-deck name such as `Polish::Lesson 1` are
+/// created as well. Fails if the name is empty or already taken.
 pub fn new_deck(name: &str, conn: &Connection) -> Result<()> {
+    validate_deck_name(name).map_err(FlashcardsError::Validation)?;
+    if deck_exists(name, conn)? {
+        return Err(FlashcardsError::Validation(format!(
+            "Deck '{}' already exists",
+            name
+        )));
+    }
+
+    for ancestor in ancestor_names(name) {
+        conn.execute(
+            "INSERT OR IGNORE INTO decks (name) VALUES (?1)",
+            params![ancestor],
+        )?;
+    }
     conn.execute("INSERT INTO decks (name) VALUES (?1)", params![name])?;
-    println!("Deck '{}' created successfully.", name);
     Ok(())
 }
 
-/// Adds a flashcard to a deck and initializes its SM-2 review data
+/// Returns the names of a deck and all of its sub-decks, parents first
+///
+/// Fails if there is no such deck and no deck below it.
+pub fn get_subtree_decks(name: &str, conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM decks
+         WHERE name = ?1 OR substr(name, 1, length(?1) + 2) = ?1 || '::'
+         ORDER BY name",
+    )?;
+    let decks = stmt
+        .query_map(params![name], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    if decks.is_empty() {
+        return Err(FlashcardsError::NotFound(format!("Deck '{}'", name)));
+    }
+    Ok(decks)
+}
+
+/// Deletes a deck and its sub-decks together with their flashcards, review
+/// data and review history
+pub fn delete_deck(name: &str, conn: &Connection) -> Result<()> {
+    ensure_deck_exists(name, conn)?;
+    let tx = conn.unchecked_transaction()?;
+
+    for deck_name in get_subtree_decks(name, &tx)? {
+        tx.execute(
+            "DELETE FROM review_log WHERE flashcard_id IN (SELECT id FROM flashcards WHERE deck_name = ?1)",
+            params![deck_name],
+        )?;
+        tx.execute(
+            "DELETE FROM review_data WHERE flashcard_id IN (SELECT id FROM flashcards WHERE deck_name = ?1)",
+            params![deck_name],
+        )?;
+        tx.execute(
+            "DELETE FROM flashcards WHERE deck_name = ?1",
+            params![deck_name],
+        )?;
+        tx.execute("DELETE FROM decks WHERE name = ?1", params![deck_name])?;
+    }
+    remove_unused_tags(&tx)?;
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Renames a deck together with its sub-decks, keeping their flashcards and
+/// review progress
+///
+/// Changing the parent part of the name moves the deck in the hierarchy, e.g.
+/// `Polish::Verbs` to `Polish::Lesson 1::Verbs`.
+pub fn rename_deck(old_name: &str, new_name: &str, conn: &Connection) -> Result<()> {
+    ensure_deck_exists(old_name, conn)?;
+    validate_deck_name(new_name).map_err(FlashcardsError::Validation)?;
+    if old_name == new_name {
+        return Ok(());
+    }
+    if is_in_subtree(new_name, old_name) {
+        return Err(FlashcardsError::Validation(format!(
+            "Deck '{}' cannot be moved into its own sub-deck",
+            old_name
+        )));
+    }
+
+    let renames: Vec<(String, String)> = get_subtree_decks(old_name, conn)?
+        .into_iter()
+        .map(|name| {
+            let renamed = format!("{}{}", new_name, &name[old_name.len()..]);
+            (name, renamed)
+        })
+        .collect();
+    for (_, renamed) in &renames {
+        if deck_exists(renamed, conn)? {
+            return Err(FlashcardsError::Validation(format!(
+                "Deck '{}' already exists",
+                renamed
+            )));
+        }
+    }
+
+    // Flashcards reference the deck name, so the new deck row is created
+    // before the cards are moved and the old one is removed afterwards
+    let tx = conn.unchecked_transaction()?;
+    for ancestor in ancestor_names(new_name) {
+        tx.execute(
+            "INSERT OR IGNORE INTO decks (name) VALUES (?1)",
+            params![ancestor],
+        )?;
+    }
+    for (name, renamed) in &renames {
+        tx.execute(
+            "INSERT INTO decks (name, scheduler, directions, new_cards_per_day, reviews_per_day)
+             SELECT ?1, scheduler, directions, new_cards_per_day, reviews_per_day
+             FROM decks WHERE name = ?2",
+            params![renamed, name],
+        )?;
+        tx.execute(
+            "UPDATE flashcards SET deck_name = ?1 WHERE deck_name = ?2",
+            params![renamed, name],
+        )?;
+        tx.execute("DELETE FROM decks WHERE name = ?1", params![name])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Creates a deck and adds all of its flashcards
+///
+/// Fails if a deck with the same name already exists.
+pub fn import_deck(deck: &Deck, conn: &Connection) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    new_deck(&deck.name, &tx)?;
+    for flashcard in &deck.flashcards {
+        let id = add_flashcard(&deck.name, &flashcard.term, &flashcard.definition, &tx)?;
+        if !flashcard.tags.is_empty() {
+            set_flashcard_tags(id, &flashcard.tags, &tx)?;
+        }
+        if flashcard.media_names().next().is_some() {
+            set_flashcard_images(
+                id,
+                flashcard.term_image.as_deref(),
+                flashcard.definition_image.as_deref(),
+                &tx,
+            )?;
+            set_flashcard_audio(
+                id,
+                flashcard.term_audio.as_deref(),
+                flashcard.definition_audio.as_deref(),
+                &tx,
+            )?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Returns the scheduler used for reviewing a deck
+pub fn get_deck_scheduler(deck_name: &str, conn: &Connection) -> Result<SchedulerKind> {
+    let scheduler: String = conn
+        .query_row(
+            "SELECT scheduler FROM decks WHERE name = ?1",
+            params![deck_name],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| FlashcardsError::NotFound(format!("Deck '{}'", deck_name)))?;
+
+    scheduler.parse().map_err(FlashcardsError::Parse)
+}
+
+/// Changes the scheduler used for reviewing a deck
+///
+/// Review data is kept, so switching back and forth preserves progress.
+pub fn set_deck_scheduler(
+    deck_name: &str,
+    scheduler: SchedulerKind,
+    conn: &Connection,
+) -> Result<()> {
+    let updated = conn.execute(
+        "UPDATE decks SET scheduler = ?1 WHERE name = ?2",
+        params![scheduler.as_str(), deck_name],
+    )?;
+    if updated == 0 {
+        return Err(FlashcardsError::NotFound(format!("Deck '{}'", deck_name)));
+    }
+    Ok(())
+}
+
+/// Returns how many new cards and reviews of a deck are studied per day
+pub fn get_deck_limits(deck_name: &str, conn: &Connection) -> Result<DailyLimits> {
+    conn.query_row(
+        "SELECT new_cards_per_day, reviews_per_day FROM decks WHERE name = ?1",
+        params![deck_name],
+        |row| {
+            Ok(DailyLimits {
+                new_cards: row.get(0)?,
+                reviews: row.get(1)?,
+            })
+        },
+    )
+    .optional()?
+    .ok_or_else(|| FlashcardsError::NotFound(format!("Deck '{}'", deck_name)))
+}
+
+/// Changes how many new cards and reviews of a deck are studied per day
+pub fn set_deck_limits(deck_name: &str, limits: &DailyLimits, conn: &Connection) -> Result<()> {
+    let updated = conn.execute(
+        "UPDATE decks SET new_cards_per_day = ?1, reviews_per_day = ?2 WHERE name = ?3",
+        params![limits.new_cards, limits.reviews, deck_name],
+    )?;
+    if updated == 0 {
+        return Err(FlashcardsError::NotFound(format!("Deck '{}'", deck_name)));
+    }
+    Ok(())
+}
+
+/// Counts the new cards and reviews of a deck studied on the current learning day
+///
+/// A card direction counts as new on the day of its first review, unless it
+/// already had an interval then (e.g. progress imported from Anki). Repeating
+/// a card within a day counts once.
+pub fn get_studied_today(deck_name: &str, conn: &Connection) -> Result<StudiedToday> {
+    let start = to_timestamp(today_start(
+        get_current_date(conn)?,
+        get_rollover_hour(conn)?,
+    ));
+
+    let (studied, new_cards): (u32, u32) = conn.query_row(
+        "SELECT COUNT(DISTINCT l.flashcard_id || ':' || l.direction),
+                COUNT(DISTINCT CASE WHEN l.previous_interval_days = 0 AND NOT EXISTS (
+                    SELECT 1 FROM review_log p
+                    WHERE p.flashcard_id = l.flashcard_id AND p.direction = l.direction
+                      AND p.id < l.id
+                ) THEN l.flashcard_id || ':' || l.direction END)
+         FROM review_log l
+         JOIN flashcards f ON f.id = l.flashcard_id
+         WHERE f.deck_name = ?1 AND l.reviewed_at >= ?2",
+        params![deck_name, start],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    Ok(StudiedToday {
+        new_cards,
+        reviews: studied - new_cards,
+    })
+}
+
+/// Retrieves the due flashcards of a deck to study now, within its daily limits
+///
+/// New cards are card directions that were never reviewed; they are introduced
+/// in the order they were added. See `get_deck_limits`.
+pub fn get_flashcards_for_session(
+    deck_name: &str,
+    conn: &Connection,
+) -> Result<Vec<(i64, Flashcard, ReviewData)>> {
+    get_matching_flashcards_for_session(deck_name, &TagFilter::Any, conn)
+}
+
+/// Like `get_flashcards_for_session`, but only with cards whose tags match `filter`
+///
+/// Cards are filtered before the daily limits are applied, so the limits are
+/// spent on matching cards only.
+pub fn get_matching_flashcards_for_session(
+    deck_name: &str,
+    filter: &TagFilter,
+    conn: &Connection,
+) -> Result<Vec<(i64, Flashcard, ReviewData)>> {
+    let mut due = get_flashcards_due_for_review(deck_name, conn)?;
+    due.retain(|(_, flashcard, _)| filter.matches(&flashcard.tags));
+    let limits = get_deck_limits(deck_name, conn)?;
+    let studied = get_studied_today(deck_name, conn)?;
+
+    let reviewed: HashSet<(i64, String)> = conn
+        .prepare(
+            "SELECT DISTINCT l.flashcard_id, l.direction FROM review_log l
+             JOIN flashcards f ON f.id = l.flashcard_id
+             WHERE f.deck_name = ?1",
+        )?
+        .query_map(params![deck_name], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    let is_new = |review: &ReviewData| {
+        review.repetitions == 0
+            && !reviewed.contains(&(review.flashcard_id, review.direction.as_str().to_string()))
+    };
+
+    Ok(apply_limits(due, is_new, &limits, &studied))
+}
+
+/// Retrieves the due flashcards matching `filter` from several decks
+///
+/// Every deck keeps its own daily limits. Each card comes with the scheduler
+/// of its deck, since the decks may use different ones.
+pub fn get_flashcards_for_tag_session(
+    deck_names: &[String],
+    filter: &TagFilter,
+    conn: &Connection,
+) -> Result<Vec<(i64, Flashcard, ReviewData, SchedulerKind)>> {
+    let mut cards = Vec::new();
+    for deck_name in deck_names {
+        let scheduler = get_deck_scheduler(deck_name, conn)?;
+        cards.extend(
+            get_matching_flashcards_for_session(deck_name, filter, conn)?
+                .into_iter()
+                .map(|(id, flashcard, review)| (id, flashcard, review, scheduler)),
+        );
+    }
+    Ok(cards)
+}
+
+/// Retrieves the due flashcards of a deck and all of its sub-decks to study now
+pub fn get_flashcards_for_deck_tree(
+    deck_name: &str,
+    conn: &Connection,
+) -> Result<Vec<(i64, Flashcard, ReviewData, SchedulerKind)>> {
+    let decks = get_subtree_decks(deck_name, conn)?;
+    get_flashcards_for_tag_session(&decks, &TagFilter::Any, conn)
+}
+
+/// Returns the directions in which the cards of a deck are studied
+pub fn get_deck_directions(deck_name: &str, conn: &Connection) -> Result<DirectionMode> {
+    let directions: String = conn
+        .query_row(
+            "SELECT directions FROM decks WHERE name = ?1",
+            params![deck_name],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| FlashcardsError::NotFound(format!("Deck '{}'", deck_name)))?;
+
+    directions.parse().map_err(FlashcardsError::Parse)
+}
+
+/// Changes the directions in which the cards of a deck are studied
+///
+/// Cards gaining a direction get fresh review data for it. Review data of
+/// directions no longer studied is kept, so re-enabling them preserves progress.
+pub fn set_deck_directions(deck_name: &str, mode: DirectionMode, conn: &Connection) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    let updated = tx.execute(
+        "UPDATE decks SET directions = ?1 WHERE name = ?2",
+        params![mode.as_str(), deck_name],
+    )?;
+    if updated == 0 {
+        return Err(FlashcardsError::NotFound(format!("Deck '{}'", deck_name)));
+    }
+    for (id, _) in get_flashcards_for_deck(deck_name, &tx)? {
+        init_review_data(id, &tx)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Returns the directions set for a single flashcard, or None if it follows its deck
+pub fn get_flashcard_directions(
+    flashcard_id: i64,
+    conn: &Connection,
+) -> Result<Option<DirectionMode>> {
+    let directions: Option<String> = conn
+        .query_row(
+            "SELECT directions FROM flashcards WHERE id = ?1",
+            params![flashcard_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| FlashcardsError::NotFound(format!("Flashcard {}", flashcard_id)))?;
+
+    directions
+        .map(|d| d.parse().map_err(FlashcardsError::Parse))
+        .transpose()
+}
+
+/// Overrides the directions of a single flashcard; None makes it follow its deck again
+pub fn set_flashcard_directions(
+    flashcard_id: i64,
+    mode: Option<DirectionMode>,
+    conn: &Connection,
+) -> Result<()> {
+    let updated = conn.execute(
+        "UPDATE flashcards SET directions = ?1 WHERE id = ?2",
+        params![mode.map(|m| m.as_str()), flashcard_id],
+    )?;
+    if updated == 0 {
+        return Err(FlashcardsError::NotFound(format!(
+            "Flashcard {}",
+            flashcard_id
+        )));
+    }
+    init_review_data(flashcard_id, conn)
+}
+
+/// Directions a flashcard is studied in, taking the deck setting into account
+fn effective_directions(flashcard_id: i64, conn: &Connection) -> Result<DirectionMode> {
+    let directions: String = conn.query_row(
+        "SELECT COALESCE(f.directions, d.directions)
+         FROM flashcards f JOIN decks d ON d.name = f.deck_name
+         WHERE f.id = ?1",
+        params![flashcard_id],
+        |row| row.get(0),
+    )?;
+    directions.parse().map_err(FlashcardsError::Parse)
+}
+
+/// Creates review data with default SM-2 values for every studied direction
+/// of a flashcard that has none yet; new directions are due immediately
+fn init_review_data(flashcard_id: i64, conn: &Connection) -> Result<()> {
+    let timestamp = to_timestamp(get_current_date(conn)?);
+    for direction in effective_directions(flashcard_id, conn)?.directions() {
+        conn.execute(
+            "INSERT OR IGNORE INTO review_data (flashcard_id, direction, easiness_factor, interval_days,
+                                                repetitions, next_review_date)
+             VALUES (?1, ?2, 2.5, 0, 0, ?3)",
+            params![flashcard_id, direction.as_str(), timestamp],
+        )?;
+    }
+    Ok(())
+}
+
+/// Adds a flashcard to a deck and initializes its review data
 ///
 /// Returns the flashcard ID. If flashcard already exists (same deck + term),
 /// it's ignored due to UNIQUE constraint.
+/// Fails if the deck does not exist or the term or definition is empty.
 pub fn add_flashcard(
     deck_name: &str,
     term: &str,
     definition: &str,
     conn: &Connection,
 ) -> Result<i64> {
+    validate_flashcard(term, definition)?;
+    ensure_deck_exists(deck_name, conn)?;
+
     // Insert flashcard (or ignore if duplicate)
     conn.execute(
         "INSERT OR IGNORE INTO flashcards (deck_name, term, definition) VALUES (?1, ?2, ?3)",
@@ -130,20 +695,272 @@ pub fn add_flashcard(
         |row| row.get(0),
     )?;
 
-    // Initialize review_data with default SM-2 values
-    let current_date = get_current_date(conn)?;
-    let timestamp = current_date
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    init_review_data(flashcard_id, conn)?;
+
+    Ok(flashcard_id)
+}
+
+fn validate_flashcard(term: &str, definition: &str) -> Result<()> {
+    if term.trim().is_empty() || definition.trim().is_empty() {
+        return Err(FlashcardsError::Validation(
+            "Term and definition cannot be empty".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Space separated tags of flashcard `f`, for use in a SELECT list
+const TAGS_COLUMN: &str = "(SELECT GROUP_CONCAT(t.name, ' ') FROM flashcard_tags ft
+      JOIN tags t ON t.id = ft.tag_id WHERE ft.flashcard_id = f.id)";
+
+/// Image and audio columns of flashcard `f`, selected right after `TAGS_COLUMN`
+const MEDIA_COLUMNS: &str = "f.term_image, f.definition_image, f.term_audio, f.definition_audio";
+
+/// Reads a column produced by `TAGS_COLUMN`, sorted so the order is stable
+fn parse_tags(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Vec<String>> {
+    let tags: Option<String> = row.get(index)?;
+    let mut tags: Vec<String> = tags
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    tags.sort_by_key(|tag| tag.to_lowercase());
+    Ok(tags)
+}
+
+/// Retrieves a single flashcard and the name of its deck
+pub fn get_flashcard(flashcard_id: i64, conn: &Connection) -> Result<(String, Flashcard)> {
+    conn.query_row(
+        &format!(
+            "SELECT f.deck_name, f.term, f.definition, {}, {} FROM flashcards f WHERE f.id = ?1",
+            TAGS_COLUMN, MEDIA_COLUMNS
+        ),
+        params![flashcard_id],
+        |row| {
+            Ok((
+                row.get(0)?,
+                Flashcard {
+                    term: row.get(1)?,
+                    definition: row.get(2)?,
+                    tags: parse_tags(row, 3)?,
+                    term_image: row.get(4)?,
+                    definition_image: row.get(5)?,
+                    term_audio: row.get(6)?,
+                    definition_audio: row.get(7)?,
+                },
+            ))
+        },
+    )
+    .optional()?
+    .ok_or_else(|| FlashcardsError::NotFound(format!("Flashcard {}", flashcard_id)))
+}
+
+/// Changes the term and definition of a flashcard, keeping its review data
+pub fn update_flashcard(
+    flashcard_id: i64,
+    term: &str,
+    definition: &str,
+    conn: &Connection,
+) -> Result<()> {
+    validate_flashcard(term, definition)?;
+    let (deck_name, _) = get_flashcard(flashcard_id, conn)?;
+    if let Some(other) = find_flashcard_id(&deck_name, term, conn)?
+        && other != flashcard_id
+    {
+        return Err(FlashcardsError::Validation(format!(
+            "Deck '{}' already has a flashcard '{}'",
+            deck_name, term
+        )));
+    }
+
+    let updated = conn.execute(
+        "UPDATE flashcards SET term = ?1, definition = ?2 WHERE id = ?3",
+        params![term, definition, flashcard_id],
+    )?;
+    if updated == 0 {
+        return Err(FlashcardsError::NotFound(format!(
+            "Flashcard {}",
+            flashcard_id
+        )));
+    }
+    Ok(())
+}
+
+/// Moves a flashcard to another deck, keeping its review progress
+///
+/// Fails if the target deck already has a flashcard with the same term.
+pub fn move_flashcard(flashcard_id: i64, target_deck: &str, conn: &Connection) -> Result<()> {
+    let (current_deck, flashcard) = get_flashcard(flashcard_id, conn)?;
+    ensure_deck_exists(target_deck, conn)?;
+    if current_deck == target_deck {
+        return Ok(());
+    }
+    if find_flashcard_id(target_deck, &flashcard.term, conn)?.is_some() {
+        return Err(FlashcardsError::Validation(format!(
+            "Deck '{}' already has a flashcard '{}'",
+            target_deck, flashcard.term
+        )));
+    }
 
     conn.execute(
-        "INSERT OR IGNORE INTO review_data (flashcard_id, easiness_factor, interval_days, repetitions, next_review_date)
-         VALUES (?1, 2.5, 0, 0, ?2)",
-        params![flashcard_id, timestamp as i64],
+        "UPDATE flashcards SET deck_name = ?1 WHERE id = ?2",
+        params![target_deck, flashcard_id],
     )?;
+    // The target deck may study more directions
+    init_review_data(flashcard_id, conn)
+}
 
-    Ok(flashcard_id)
+/// Looks up the ID of a flashcard by its deck and term
+pub fn find_flashcard_id(deck_name: &str, term: &str, conn: &Connection) -> Result<Option<i64>> {
+    let id = conn
+        .query_row(
+            "SELECT id FROM flashcards WHERE deck_name = ?1 AND term = ?2",
+            params![deck_name, term],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(id)
+}
+
+/// Deletes a flashcard together with its review data and review history
+pub fn delete_flashcard(flashcard_id: i64, conn: &Connection) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    tx.execute(
+        "DELETE FROM review_log WHERE flashcard_id = ?1",
+        params![flashcard_id],
+    )?;
+    tx.execute(
+        "DELETE FROM review_data WHERE flashcard_id = ?1",
+        params![flashcard_id],
+    )?;
+    let deleted = tx.execute(
+        "DELETE FROM flashcards WHERE id = ?1",
+        params![flashcard_id],
+    )?;
+    if deleted == 0 {
+        return Err(FlashcardsError::NotFound(format!(
+            "Flashcard {}",
+            flashcard_id
+        )));
+    }
+    remove_unused_tags(&tx)?;
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Replaces the tags of a flashcard
+///
+/// Tags are trimmed and duplicates (ignoring case) dropped. A tag already used
+/// by another card keeps the spelling it was first created with.
+/// Fails if a tag is not a single word, see `validate_tag`.
+pub fn set_flashcard_tags(flashcard_id: i64, tags: &[String], conn: &Connection) -> Result<()> {
+    get_flashcard(flashcard_id, conn)?;
+
+    let mut seen = HashSet::new();
+    let mut unique = Vec::new();
+    for tag in tags.iter().map(|tag| tag.trim()) {
+        validate_tag(tag).map_err(FlashcardsError::Validation)?;
+        if seen.insert(tag.to_lowercase()) {
+            unique.push(tag);
+        }
+    }
+
+    conn.execute(
+        "DELETE FROM flashcard_tags WHERE flashcard_id = ?1",
+        params![flashcard_id],
+    )?;
+    for tag in unique {
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+            params![tag],
+        )?;
+        conn.execute(
+            "INSERT INTO flashcard_tags (flashcard_id, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            params![flashcard_id, tag],
+        )?;
+    }
+    remove_unused_tags(conn)
+}
+
+/// Sets the images shown with the term and the definition of a flashcard
+///
+/// Images are named by the media store, see `MediaStore::add_file`; None removes one.
+pub fn set_flashcard_images(
+    flashcard_id: i64,
+    term_image: Option<&str>,
+    definition_image: Option<&str>,
+    conn: &Connection,
+) -> Result<()> {
+    set_media_columns(
+        flashcard_id,
+        ["term_image", "definition_image"],
+        [term_image, definition_image],
+        conn,
+    )
+}
+
+/// Sets the audio clips played with the term and the definition of a flashcard
+///
+/// Clips are named by the media store, see `MediaStore::add_file`; None removes one.
+pub fn set_flashcard_audio(
+    flashcard_id: i64,
+    term_audio: Option<&str>,
+    definition_audio: Option<&str>,
+    conn: &Connection,
+) -> Result<()> {
+    set_media_columns(
+        flashcard_id,
+        ["term_audio", "definition_audio"],
+        [term_audio, definition_audio],
+        conn,
+    )
+}
+
+/// Stores media names in the term and definition columns of a flashcard
+fn set_media_columns(
+    flashcard_id: i64,
+    columns: [&str; 2],
+    names: [Option<&str>; 2],
+    conn: &Connection,
+) -> Result<()> {
+    get_flashcard(flashcard_id, conn)?;
+    for name in names.iter().flatten() {
+        validate_media_name(name)?;
+    }
+    conn.execute(
+        &format!(
+            "UPDATE flashcards SET {} = ?1, {} = ?2 WHERE id = ?3",
+            columns[0], columns[1]
+        ),
+        params![names[0], names[1], flashcard_id],
+    )?;
+    Ok(())
+}
+
+/// Returns every tag in use with the number of flashcards carrying it, by name
+pub fn get_all_tags(conn: &Connection) -> Result<Vec<(String, u32)>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.name, COUNT(*) FROM tags t
+         JOIN flashcard_tags ft ON ft.tag_id = t.id
+         GROUP BY t.id
+         ORDER BY t.name",
+    )?;
+    let tags = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(tags)
+}
+
+/// Deletes tags no flashcard carries anymore
+fn remove_unused_tags(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM flashcard_tags)",
+        [],
+    )?;
+    Ok(())
 }
 
 /// Retrieves all flashcards for a given deck
@@ -153,8 +970,10 @@ pub fn get_flashcards_for_deck(
     deck_name: &str,
     conn: &Connection,
 ) -> Result<Vec<(i64, Flashcard)>> {
-    let mut stmt =
-        conn.prepare("SELECT id, term, definition FROM flashcards WHERE deck_name = ?1")?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT f.id, f.term, f.definition, {}, {} FROM flashcards f WHERE f.deck_name = ?1",
+        TAGS_COLUMN, MEDIA_COLUMNS
+    ))?;
 
     let flashcards = stmt
         .query_map(params![deck_name], |row| {
@@ -163,90 +982,355 @@ pub fn get_flashcards_for_deck(
                 Flashcard {
                     term: row.get(1)?,
                     definition: row.get(2)?,
+                    tags: parse_tags(row, 3)?,
+                    term_image: row.get(4)?,
+                    definition_image: row.get(5)?,
+                    term_audio: row.get(6)?,
+                    definition_audio: row.get(7)?,
                 },
             ))
         })?
-        .collect::<Result<Vec<(i64, Flashcard)>>>()?;
+        .collect::<rusqlite::Result<Vec<(i64, Flashcard)>>>()?;
 
     Ok(flashcards)
 }
 
-/// Updates SM-2 review data for a flashcard after a learning session
+/// Updates review data for one direction of a flashcard after a learning session
 pub fn update_review_data(review_data: &ReviewData, conn: &Connection) -> Result<()> {
-    let timestamp = review_data
-        .next_review_date
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
+    let timestamp = to_timestamp(review_data.next_review_date);
 
-    conn.execute(
+    let updated = conn.execute(
         "UPDATE review_data
-         SET easiness_factor = ?1, interval_days = ?2, repetitions = ?3, next_review_date = ?4
-         WHERE flashcard_id = ?5",
+         SET easiness_factor = ?1, interval_days = ?2, repetitions = ?3, next_review_date = ?4,
+             stability = ?5, difficulty = ?6
+         WHERE flashcard_id = ?7 AND direction = ?8",
         params![
             review_data.easiness_factor,
             review_data.interval_days,
             review_data.repetitions,
             timestamp,
-            review_data.flashcard_id
+            review_data.stability,
+            review_data.difficulty,
+            review_data.flashcard_id,
+            review_data.direction.as_str()
         ],
     )?;
+    if updated == 0 {
+        return Err(FlashcardsError::NotFound(format!(
+            "Review data of flashcard {} ({})",
+            review_data.flashcard_id, review_data.direction
+        )));
+    }
 
     Ok(())
 }
 
+/// Stores the result of grading a card: new review data and its history entry
+///
+/// Both writes happen in one transaction so progress and history never diverge.
+pub fn record_review(
+    review_data: &ReviewData,
+    entry: &ReviewLogEntry,
+    conn: &Connection,
+) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    update_review_data(review_data, &tx)?;
+    add_review_log_entry(entry, &tx)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Appends an entry to the review history
+///
+/// Returns the ID of the new log row.
+pub fn add_review_log_entry(entry: &ReviewLogEntry, conn: &Connection) -> Result<i64> {
+    let timestamp = to_timestamp(entry.reviewed_at);
+
+    conn.execute(
+        "INSERT INTO review_log (flashcard_id, direction, reviewed_at, quality, previous_interval_days,
+                                 new_interval_days, previous_easiness_factor, new_easiness_factor,
+                                 time_taken_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            entry.flashcard_id,
+            entry.direction.as_str(),
+            timestamp,
+            entry.quality,
+            entry.previous_interval_days,
+            entry.new_interval_days,
+            entry.previous_easiness_factor,
+            entry.new_easiness_factor,
+            entry.time_taken.as_millis() as i64
+        ],
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+const REVIEW_DATA_COLUMNS: &str =
+    "r.flashcard_id, r.direction, r.easiness_factor, r.interval_days, r.repetitions,
+     r.next_review_date, r.stability, r.difficulty";
+
+/// Reads the columns of `REVIEW_DATA_COLUMNS` at the start of a row
+fn review_data_from_row(row: &rusqlite::Row) -> rusqlite::Result<ReviewData> {
+    Ok(ReviewData {
+        flashcard_id: row.get(0)?,
+        direction: parse_direction(row, 1)?,
+        easiness_factor: row.get(2)?,
+        interval_days: row.get(3)?,
+        repetitions: row.get(4)?,
+        next_review_date: from_timestamp(row.get(5)?),
+        stability: row.get(6)?,
+        difficulty: row.get(7)?,
+    })
+}
+
+const REVIEW_LOG_COLUMNS: &str =
+    "l.id, l.flashcard_id, l.reviewed_at, l.quality, l.previous_interval_days,
+     l.new_interval_days, l.previous_easiness_factor, l.new_easiness_factor, l.time_taken_ms,
+     l.direction";
+
+fn review_log_from_row(row: &rusqlite::Row) -> rusqlite::Result<ReviewLogEntry> {
+    Ok(ReviewLogEntry {
+        id: row.get(0)?,
+        flashcard_id: row.get(1)?,
+        reviewed_at: from_timestamp(row.get(2)?),
+        quality: row.get(3)?,
+        previous_interval_days: row.get(4)?,
+        new_interval_days: row.get(5)?,
+        previous_easiness_factor: row.get(6)?,
+        new_easiness_factor: row.get(7)?,
+        time_taken: Duration::from_millis(row.get::<_, i64>(8)? as u64),
+        direction: parse_direction(row, 9)?,
+    })
+}
+
+/// Reads a direction column, reporting unknown values as conversion failures
+fn parse_direction(row: &rusqlite::Row, index: usize) -> rusqlite::Result<CardDirection> {
+    let value: String = row.get(index)?;
+    value.parse().map_err(|e: String| {
+        rusqlite::Error::FromSqlConversionFailure(
+            index,
+            rusqlite::types::Type::Text,
+            FlashcardsError::Parse(e).into(),
+        )
+    })
+}
+
+/// Retrieves the review history of a single flashcard, oldest first
+pub fn get_review_log_for_flashcard(
+    flashcard_id: i64,
+    conn: &Connection,
+) -> Result<Vec<ReviewLogEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM review_log l WHERE l.flashcard_id = ?1 ORDER BY l.reviewed_at, l.id",
+        REVIEW_LOG_COLUMNS
+    ))?;
+
+    let entries = stmt
+        .query_map(params![flashcard_id], review_log_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(entries)
+}
+
+/// Retrieves the review history of all flashcards in a deck, oldest first
+pub fn get_review_log_for_deck(deck_name: &str, conn: &Connection) -> Result<Vec<ReviewLogEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM review_log l
+         JOIN flashcards f ON f.id = l.flashcard_id
+         WHERE f.deck_name = ?1
+         ORDER BY l.reviewed_at, l.id",
+        REVIEW_LOG_COLUMNS
+    ))?;
+
+    let entries = stmt
+        .query_map(params![deck_name], review_log_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(entries)
+}
+
+/// Retrieves the complete review history, oldest first
+pub fn get_review_log(conn: &Connection) -> Result<Vec<ReviewLogEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM review_log l ORDER BY l.reviewed_at, l.id",
+        REVIEW_LOG_COLUMNS
+    ))?;
+
+    let entries = stmt
+        .query_map([], review_log_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(entries)
+}
+
+/// Projects the daily workload of a deck, see `simulate_workload`
+///
+/// Card directions without review history count as new cards; the deck's
+/// scheduler, daily limits and the cards studied today are taken into account.
+pub fn get_workload_forecast(
+    deck_name: &str,
+    options: &SimulationOptions,
+    conn: &Connection,
+) -> Result<Vec<DayWorkload>> {
+    ensure_deck_exists(deck_name, conn)?;
+    if let Some(p) = options.recall_probability
+        && !(p > 0.0 && p <= 1.0)
+    {
+        return Err(FlashcardsError::Validation(format!(
+            "Recall probability must be above 0 and at most 1, got {}",
+            p
+        )));
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, EXISTS (
+                    SELECT 1 FROM review_log l
+                    WHERE l.flashcard_id = r.flashcard_id AND l.direction = r.direction
+                )
+         FROM flashcards f
+         JOIN decks d ON d.name = f.deck_name
+         JOIN review_data r ON f.id = r.flashcard_id
+         WHERE f.deck_name = ?1
+           AND COALESCE(f.directions, d.directions) IN (r.direction, 'both')",
+        REVIEW_DATA_COLUMNS
+    ))?;
+    let cards = stmt
+        .query_map(params![deck_name], |row| {
+            Ok((review_data_from_row(row)?, row.get::<_, bool>(8)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let (reviewed, new_cards): (Vec<_>, Vec<_>) =
+        cards.into_iter().partition(|(_, reviewed)| *reviewed);
+    let reviewed: Vec<ReviewData> = reviewed.into_iter().map(|(review, _)| review).collect();
+
+    Ok(simulate_workload(
+        &reviewed,
+        new_cards.len(),
+        &get_deck_limits(deck_name, conn)?,
+        &get_studied_today(deck_name, conn)?,
+        get_current_date(conn)?,
+        get_rollover_hour(conn)?,
+        get_deck_scheduler(deck_name, conn)?.scheduler().as_ref(),
+        options,
+    ))
+}
+
+/// Computes the statistics of all decks from their review data and history
+///
+/// Only the directions in which cards are studied are counted.
+pub fn get_statistics(conn: &Connection) -> Result<Statistics> {
+    let decks = get_all_decks(conn)?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, f.deck_name
+         FROM flashcards f
+         JOIN decks d ON d.name = f.deck_name
+         JOIN review_data r ON f.id = r.flashcard_id
+         WHERE COALESCE(f.directions, d.directions) IN (r.direction, 'both')",
+        REVIEW_DATA_COLUMNS
+    ))?;
+    let cards = stmt
+        .query_map([], |row| Ok((row.get(8)?, review_data_from_row(row)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Statistics::compute(
+        &decks,
+        &cards,
+        &get_review_log(conn)?,
+        get_current_date(conn)?,
+        get_rollover_hour(conn)?,
+    ))
+}
+
 /// Retrieves flashcards due for review in a deck
 ///
-/// Returns flashcards where next_review_date <= current_date,
-/// ordered by next_review_date (oldest first).
+/// Returns flashcards due on the current learning day,
+/// ordered by next_review_date (oldest first). A flashcard studied in both
+/// directions appears once per due direction; see `ReviewData::direction`.
+///
+/// A card is due if its next_review_date is before the start of the next
+/// learning day, see `get_rollover_hour`.
 pub fn get_flashcards_due_for_review(
     deck_name: &str,
     conn: &Connection,
 ) -> Result<Vec<(i64, Flashcard, ReviewData)>> {
-    let current_date = get_current_date(conn)?;
-    let current_timestamp = current_date
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
+    let cutoff = due_cutoff(get_current_date(conn)?, get_rollover_hour(conn)?);
+    let cutoff_timestamp = to_timestamp(cutoff);
 
-    let mut stmt = conn.prepare(
-        "SELECT f.id, f.term, f.definition, r.easiness_factor, r.interval_days, r.repetitions, r.next_review_date
+    let mut stmt = conn.prepare(&format!(
+        "SELECT f.id, f.term, f.definition, r.easiness_factor, r.interval_days, r.repetitions, r.next_review_date,
+                r.stability, r.difficulty, r.direction, {}, {}
          FROM flashcards f
+         JOIN decks d ON d.name = f.deck_name
          JOIN review_data r ON f.id = r.flashcard_id
-         WHERE f.deck_name = ?1 AND r.next_review_date <= ?2
-         ORDER BY r.next_review_date ASC"
-    )?;
+         WHERE f.deck_name = ?1 AND r.next_review_date < ?2
+           AND COALESCE(f.directions, d.directions) IN (r.direction, 'both')
+         ORDER BY r.next_review_date ASC",
+        TAGS_COLUMN, MEDIA_COLUMNS
+    ))?;
 
     let flashcards = stmt
-        .query_map(params![deck_name, current_timestamp], |row| {
+        .query_map(params![deck_name, cutoff_timestamp], |row| {
             let id: i64 = row.get(0)?;
             Ok((
                 id,
                 Flashcard {
                     term: row.get(1)?,
                     definition: row.get(2)?,
+                    tags: parse_tags(row, 10)?,
+                    term_image: row.get(11)?,
+                    definition_image: row.get(12)?,
+                    term_audio: row.get(13)?,
+                    definition_audio: row.get(14)?,
                 },
                 ReviewData {
                     flashcard_id: id,
+                    direction: parse_direction(row, 9)?,
                     easiness_factor: row.get(3)?,
                     interval_days: row.get(4)?,
                     repetitions: row.get(5)?,
-                    next_review_date: SystemTime::UNIX_EPOCH
-                        + Duration::from_secs(row.get::<_, i64>(6)? as u64),
+                    next_review_date: from_timestamp(row.get(6)?),
+                    stability: row.get(7)?,
+                    difficulty: row.get(8)?,
                 },
             ))
         })?
-        .collect::<Result<Vec<_>>>()?;
+        .collect::<rusqlite::Result<Vec<_>>>()?;
 
     Ok(flashcards)
 }
 
+/// Counts the due card directions of every deck, not including sub-decks
+///
+/// Decks without due cards are left out. Daily limits are not applied.
+pub fn get_due_counts(conn: &Connection) -> Result<HashMap<String, usize>> {
+    let cutoff = due_cutoff(get_current_date(conn)?, get_rollover_hour(conn)?);
+
+    let mut stmt = conn.prepare(
+        "SELECT f.deck_name, COUNT(*)
+         FROM flashcards f
+         JOIN decks d ON d.name = f.deck_name
+         JOIN review_data r ON f.id = r.flashcard_id
+         WHERE r.next_review_date < ?1
+           AND COALESCE(f.directions, d.directions) IN (r.direction, 'both')
+         GROUP BY f.deck_name",
+    )?;
+    let counts = stmt
+        .query_map(params![to_timestamp(cutoff)], |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)? as usize))
+        })?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+    Ok(counts)
+}
+
 /// Retrieves all deck names from database
 pub fn get_all_decks(conn: &Connection) -> Result<Vec<String>> {
-    let mut stmt = conn.prepare("SELECT name FROM decks")?;
+    let mut stmt = conn.prepare("SELECT name FROM decks ORDER BY name")?;
     let decks = stmt
         .query_map([], |row| row.get(0))?
-        .collect::<Result<Vec<String>>>()?;
+        .collect::<rusqlite::Result<Vec<String>>>()?;
     Ok(decks)
 }
 
@@ -254,10 +1338,10 @@ pub fn get_all_decks(conn: &Connection) -> Result<Vec<String>> {
 ///
 /// Does not load SM-2 review data - that's fetched separately when starting a learning session.
 pub fn load_all_decks(conn: &Connection) -> Result<DeckSet> {
-    let mut stmt = conn.prepare("SELECT name FROM decks")?;
+    let mut stmt = conn.prepare("SELECT name FROM decks ORDER BY name")?;
     let deck_names = stmt
         .query_map([], |row| row.get::<_, String>(0))?
-        .collect::<Result<Vec<String>>>()?;
+        .collect::<rusqlite::Result<Vec<String>>>()?;
 
     let mut decks = Vec::new();
 
@@ -274,3 +1358,372 @@ pub fn load_all_decks(conn: &Connection) -> Result<DeckSet> {
 
     Ok(DeckSet { decks })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::LearningSession;
+    use crate::models::test_support::review_log_entry;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn deck_with_card() -> (Connection, i64) {
+        let conn = init_in_memory_database().unwrap();
+        new_deck("Polish", &conn).unwrap();
+        let id = add_flashcard("Polish", "kot", "cat", &conn).unwrap();
+        (conn, id)
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let (conn, _) = deck_with_card();
+
+        assert!(matches!(
+            new_deck("  ", &conn),
+            Err(FlashcardsError::Validation(_))
+        ));
+        assert!(matches!(
+            new_deck("Polish", &conn),
+            Err(FlashcardsError::Validation(_))
+        ));
+        assert!(matches!(
+            add_flashcard("Polish", "pies", "", &conn),
+            Err(FlashcardsError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_clock_modes() {
+        let conn = init_in_memory_database().unwrap();
+        assert_eq!(get_clock_mode(&conn).unwrap(), ClockMode::System);
+        assert!(matches!(
+            advance_day(&conn),
+            Err(FlashcardsError::Validation(_))
+        ));
+
+        set_clock_mode(ClockMode::Simulated, &conn).unwrap();
+        let today = get_current_date(&conn).unwrap();
+        advance_day(&conn).unwrap();
+        assert_eq!(
+            get_current_date(&conn).unwrap(),
+            today + Duration::from_secs(24 * 60 * 60)
+        );
+
+        set_clock_mode(ClockMode::System, &conn).unwrap();
+        assert!(get_current_date(&conn).unwrap() < today + Duration::from_secs(60 * 60));
+    }
+
+    #[test]
+    fn test_not_found_errors() {
+        let (conn, id) = deck_with_card();
+
+        assert!(matches!(
+            add_flashcard("Missing", "pies", "dog", &conn),
+            Err(FlashcardsError::NotFound(_))
+        ));
+        assert!(matches!(
+            get_flashcard(id + 1, &conn),
+            Err(FlashcardsError::NotFound(_))
+        ));
+        assert!(matches!(
+            delete_deck("Missing", &conn),
+            Err(FlashcardsError::NotFound(_))
+        ));
+        assert!(matches!(
+            get_deck_scheduler("Missing", &conn),
+            Err(FlashcardsError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_deck_scheduler_round_trip() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // A deck saved before decks had a scheduler column
+        conn.execute_batch(
+            "CREATE TABLE decks (name TEXT PRIMARY KEY);
+             INSERT INTO decks (name) VALUES ('Old Deck');",
+        )
+        .unwrap();
+        prepare_connection(&mut conn).unwrap();
+        assert_eq!(
+            get_deck_scheduler("Old Deck", &conn).unwrap(),
+            SchedulerKind::Sm2
+        );
+
+        set_deck_scheduler("Old Deck", SchedulerKind::Fsrs, &conn).unwrap();
+        assert_eq!(
+            get_deck_scheduler("Old Deck", &conn).unwrap(),
+            SchedulerKind::Fsrs
+        );
+        assert!(matches!(
+            set_deck_scheduler("Missing", SchedulerKind::Fsrs, &conn),
+            Err(FlashcardsError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_record_review_writes_history() {
+        let (conn, id) = deck_with_card();
+        let (_, _, review) = get_flashcards_due_for_review("Polish", &conn)
+            .unwrap()
+            .remove(0);
+        let now = get_current_date(&conn).unwrap();
+        let next = crate::models::sm2::calculate_next_review(&review, 4, now);
+        let entry = ReviewLogEntry {
+            time_taken: Duration::from_millis(1500),
+            ..review_log_entry(&review, &next, 4, now)
+        };
+
+        record_review(&next, &entry, &conn).unwrap();
+
+        assert!(
+            get_flashcards_due_for_review("Polish", &conn)
+                .unwrap()
+                .is_empty()
+        );
+        let log = get_review_log_for_flashcard(id, &conn).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].quality, 4);
+        assert_eq!(log[0].new_interval_days, 1);
+        assert_eq!(log[0].time_taken, Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_grading_logs_one_review() {
+        let (conn, id) = deck_with_card();
+        let cards = get_flashcards_due_for_review("Polish", &conn).unwrap();
+        let before = cards[0].2.clone();
+        let now = get_current_date(&conn).unwrap();
+        let after = crate::models::sm2::calculate_next_review(&before, 5, now);
+        let conn = Arc::new(Mutex::new(conn));
+        let mut session = LearningSession::new_from_due_cards(
+            "Polish".to_string(),
+            cards,
+            SchedulerKind::Sm2.scheduler(),
+            Arc::clone(&conn),
+        );
+
+        session.grade_current_card(5).unwrap();
+
+        let log = get_review_log_for_flashcard(id, &conn.lock().unwrap()).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].quality, 5);
+        assert_eq!(log[0].previous_interval_days, before.interval_days);
+        assert_eq!(log[0].new_interval_days, after.interval_days);
+        assert_eq!(log[0].previous_easiness_factor, before.easiness_factor);
+        assert_eq!(log[0].new_easiness_factor, after.easiness_factor);
+    }
+
+    #[test]
+    fn test_failed_log_entry_keeps_review_data() {
+        let (conn, _) = deck_with_card();
+        let (_, _, review) = get_flashcards_due_for_review("Polish", &conn)
+            .unwrap()
+            .remove(0);
+        let now = get_current_date(&conn).unwrap();
+        let next = crate::models::sm2::calculate_next_review(&review, 5, now);
+        let entry = review_log_entry(&review, &next, 5, now);
+        conn.execute("DROP TABLE review_log", []).unwrap();
+
+        assert!(record_review(&next, &entry, &conn).is_err());
+        let (_, _, stored) = get_flashcards_due_for_review("Polish", &conn)
+            .unwrap()
+            .remove(0);
+        assert_eq!(stored.interval_days, review.interval_days);
+        assert_eq!(stored.repetitions, review.repetitions);
+    }
+
+    #[test]
+    fn test_directions_are_scheduled_independently() {
+        let (conn, id) = deck_with_card();
+        let due = |conn: &Connection| -> Vec<CardDirection> {
+            get_flashcards_due_for_review("Polish", conn)
+                .unwrap()
+                .into_iter()
+                .map(|(_, _, review)| review.direction)
+                .collect()
+        };
+        assert_eq!(due(&conn), vec![CardDirection::Forward]);
+
+        set_deck_directions("Polish", DirectionMode::Both, &conn).unwrap();
+        assert_eq!(due(&conn).len(), 2);
+
+        let (_, _, reverse) = get_flashcards_due_for_review("Polish", &conn)
+            .unwrap()
+            .into_iter()
+            .find(|(_, _, review)| review.direction == CardDirection::Reverse)
+            .unwrap();
+        let now = get_current_date(&conn).unwrap();
+        let next = crate::models::sm2::calculate_next_review(&reverse, 5, now);
+        update_review_data(&next, &conn).unwrap();
+        assert_eq!(due(&conn), vec![CardDirection::Forward]);
+
+        set_flashcard_directions(id, Some(DirectionMode::Reverse), &conn).unwrap();
+        assert!(due(&conn).is_empty());
+        assert_eq!(
+            get_flashcard_directions(id, &conn).unwrap(),
+            Some(DirectionMode::Reverse)
+        );
+    }
+
+    #[test]
+    fn test_session_respects_daily_limits() {
+        let conn = init_in_memory_database().unwrap();
+        new_deck("Polish", &conn).unwrap();
+        for i in 0..25 {
+            add_flashcard("Polish", &format!("term {}", i), "definition", &conn).unwrap();
+        }
+        let limits = DailyLimits {
+            new_cards: 10,
+            reviews: 5,
+        };
+        set_deck_limits("Polish", &limits, &conn).unwrap();
+
+        let session = get_flashcards_for_session("Polish", &conn).unwrap();
+        assert_eq!(session.len(), 10);
+
+        let (_, _, review) = session[0].clone();
+        let now = get_current_date(&conn).unwrap();
+        let next = crate::models::sm2::calculate_next_review(&review, 4, now);
+        let entry = review_log_entry(&review, &next, 4, now);
+        record_review(&next, &entry, &conn).unwrap();
+
+        assert_eq!(
+            get_studied_today("Polish", &conn).unwrap(),
+            StudiedToday {
+                new_cards: 1,
+                reviews: 0
+            }
+        );
+        assert_eq!(
+            get_flashcards_for_session("Polish", &conn).unwrap().len(),
+            9
+        );
+    }
+
+    #[test]
+    fn test_tags_select_session_cards() {
+        let (conn, kot) = deck_with_card();
+        let pies = add_flashcard("Polish", "pies", "dog", &conn).unwrap();
+        add_flashcard("Polish", "biegać", "to run", &conn).unwrap();
+        new_deck("German", &conn).unwrap();
+        let hund = add_flashcard("German", "Hund", "dog", &conn).unwrap();
+
+        let tags = |list: &str| -> Vec<String> { list.split(' ').map(str::to_string).collect() };
+        set_flashcard_tags(kot, &tags("noun lesson1 Noun"), &conn).unwrap();
+        set_flashcard_tags(pies, &tags("noun lesson2"), &conn).unwrap();
+        set_flashcard_tags(hund, &tags("NOUN lesson1"), &conn).unwrap();
+        assert!(matches!(
+            set_flashcard_tags(kot, &tags("not"), &conn),
+            Err(FlashcardsError::Validation(_))
+        ));
+
+        assert_eq!(
+            get_flashcard(kot, &conn).unwrap().1.tags,
+            tags("lesson1 noun")
+        );
+        assert_eq!(
+            get_all_tags(&conn).unwrap(),
+            vec![
+                ("lesson1".to_string(), 2),
+                ("lesson2".to_string(), 1),
+                ("noun".to_string(), 3)
+            ]
+        );
+
+        let filter: TagFilter = "noun and not lesson2".parse().unwrap();
+        let decks = vec!["Polish".to_string(), "German".to_string()];
+        let ids: Vec<i64> = get_flashcards_for_tag_session(&decks, &filter, &conn)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _, _, _)| id)
+            .collect();
+        assert_eq!(ids, vec![kot, hund]);
+
+        set_flashcard_tags(pies, &[], &conn).unwrap();
+        assert_eq!(get_all_tags(&conn).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rename_deck_keeps_cards() {
+        let (conn, id) = deck_with_card();
+
+        rename_deck("Polish", "Polski", &conn).unwrap();
+
+        assert!(!deck_exists("Polish", &conn).unwrap());
+        assert_eq!(get_flashcard(id, &conn).unwrap().0, "Polski");
+        assert_eq!(
+            get_flashcards_due_for_review("Polski", &conn)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_sub_decks() {
+        let (conn, id) = deck_with_card();
+        new_deck("Polish::Lesson 1::Verbs", &conn).unwrap();
+        add_flashcard("Polish::Lesson 1::Verbs", "biec", "to run", &conn).unwrap();
+        new_deck("Polish 2", &conn).unwrap();
+        assert!(matches!(
+            new_deck("Polish::::Nouns", &conn),
+            Err(FlashcardsError::Validation(_))
+        ));
+
+        assert_eq!(
+            get_subtree_decks("Polish", &conn).unwrap(),
+            vec!["Polish", "Polish::Lesson 1", "Polish::Lesson 1::Verbs"]
+        );
+        assert_eq!(
+            get_flashcards_for_deck_tree("Polish", &conn).unwrap().len(),
+            2
+        );
+        assert_eq!(get_due_counts(&conn).unwrap()["Polish::Lesson 1::Verbs"], 1);
+
+        assert!(matches!(
+            rename_deck("Polish", "Polish::Old", &conn),
+            Err(FlashcardsError::Validation(_))
+        ));
+        rename_deck("Polish::Lesson 1", "Archive::Lesson 1", &conn).unwrap();
+        assert_eq!(
+            get_all_decks(&conn).unwrap(),
+            vec![
+                "Archive",
+                "Archive::Lesson 1",
+                "Archive::Lesson 1::Verbs",
+                "Polish",
+                "Polish 2"
+            ]
+        );
+
+        delete_deck("Archive", &conn).unwrap();
+        assert_eq!(get_all_decks(&conn).unwrap(), vec!["Polish", "Polish 2"]);
+        assert_eq!(get_flashcard(id, &conn).unwrap().0, "Polish");
+    }
+
+    #[test]
+    fn test_move_flashcard_rejects_duplicate_term() {
+        let (conn, id) = deck_with_card();
+        new_deck("Animals", &conn).unwrap();
+        add_flashcard("Animals", "kot", "cat", &conn).unwrap();
+        new_deck("Other", &conn).unwrap();
+
+        assert!(matches!(
+            move_flashcard(id, "Animals", &conn),
+            Err(FlashcardsError::Validation(_))
+        ));
+        move_flashcard(id, "Other", &conn).unwrap();
+        assert_eq!(get_flashcard(id, &conn).unwrap().0, "Other");
+    }
+
+    #[test]
+    fn test_delete_deck_removes_cards() {
+        let (conn, id) = deck_with_card();
+
+        delete_deck("Polish", &conn).unwrap();
+
+        assert!(!deck_exists("Polish", &conn).unwrap());
+        assert!(get_flashcard(id, &conn).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! Resolution of the database file location.
+//!
+//! The database path is chosen in this order:
+//! 1. An explicit path (`--db` flag)
+//! 2. A named profile (`--profile` flag)
+//! 3. The `FLASHCARDS_DB` environment variable
+//! 4. The `FLASHCARDS_PROFILE` environment variable
+//! 5. The `default` profile
+//!
+//! Profiles live in the platform data directory (`$XDG_DATA_HOME/flashcards`
+//! on Linux), one database per profile, e.g. one per learner. A database left
+//! in the working directory by an older version is copied to the `default`
+//! profile the first time it is used, see `adopt_legacy_database`.
+
+use crate::error::{FlashcardsError, Result};
+use std::path::{Path, PathBuf};
+
+pub const DATABASE_ENV: &str = "FLASHCARDS_DB";
+pub const PROFILE_ENV: &str = "FLASHCARDS_PROFILE";
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// File name used by versions that stored the database in the working directory
+pub const LEGACY_DATABASE_FILE: &str = "db.sqlite3";
+
+const PROFILE_EXTENSION: &str = "sqlite3";
+
+/// Directory holding all application data
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("flashcards"))
+}
+
+/// Directory holding one database per profile
+pub fn profiles_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("profiles"))
+}
+
+/// Checks that a profile name can be safely used as a file name
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(FlashcardsError::Validation(format!(
+            "Invalid profile name '{}': use letters, digits, '-' and '_' only",
+            name
+        )))
+    }
+}
+
+/// Database path of a named profile
+pub fn profile_path(name: &str) -> Result<PathBuf> {
+    validate_profile_name(name)?;
+    let dir =
+        profiles_dir().ok_or_else(|| FlashcardsError::NotFound("Data directory".to_string()))?;
+    Ok(dir.join(format!("{}.{}", name, PROFILE_EXTENSION)))
+}
+
+/// Names of all profiles that have a database, sorted alphabetically
+pub fn list_profiles() -> Vec<String> {
+    let Some(dir) = profiles_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut profiles: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == PROFILE_EXTENSION))
+        .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    profiles.sort();
+    profiles
+}
+
+/// Copies a database from an older version to `path` if there is no database there yet
+///
+/// The legacy file is left in place as a backup. The copy only appears at
+/// `path` once it is complete. Returns true if the database was copied.
+pub fn adopt_legacy_database(legacy: &Path, path: &Path) -> Result<bool> {
+    if path.exists() || !legacy.is_file() {
+        return Ok(false);
+    }
+
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    let partial = path.with_extension("partial");
+    std::fs::copy(legacy, &partial)?;
+    std::fs::rename(&partial, path)?;
+    Ok(true)
+}
+
+/// Where the database comes from, before it is turned into a path
+#[derive(Debug, PartialEq)]
+pub enum DatabaseLocation {
+    Path(PathBuf),
+    Profile(String),
+}
+
+impl DatabaseLocation {
+    /// Applies the precedence rules to flags and environment values
+    pub fn choose(
+        db_flag: Option<&Path>,
+        profile_flag: Option<&str>,
+        db_env: Option<&str>,
+        profile_env: Option<&str>,
+    ) -> Self {
+        if let Some(path) = db_flag {
+            DatabaseLocation::Path(path.to_path_buf())
+        } else if let Some(profile) = profile_flag {
+            DatabaseLocation::Profile(profile.to_string())
+        } else if let Some(path) = db_env.filter(|v| !v.is_empty()) {
+            DatabaseLocation::Path(PathBuf::from(path))
+        } else if let Some(profile) = profile_env.filter(|v| !v.is_empty()) {
+            DatabaseLocation::Profile(profile.to_string())
+        } else {
+            DatabaseLocation::Profile(DEFAULT_PROFILE.to_string())
+        }
+    }
+
+    /// Reads the environment variables and applies the precedence rules
+    pub fn from_args(db_flag: Option<&Path>, profile_flag: Option<&str>) -> Self {
+        let db_env = std::env::var(DATABASE_ENV).ok();
+        let profile_env = std::env::var(PROFILE_ENV).ok();
+        Self::choose(
+            db_flag,
+            profile_flag,
+            db_env.as_deref(),
+            profile_env.as_deref(),
+        )
+    }
+
+    /// Database file path for this location
+    pub fn path(&self) -> Result<PathBuf> {
+        match self {
+            DatabaseLocation::Path(path) => Ok(path.clone()),
+            DatabaseLocation::Profile(name) => profile_path(name),
+        }
+    }
+
+    /// Profile name, if the location is a profile
+    pub fn profile(&self) -> Option<&str> {
+        match self {
+            DatabaseLocation::Path(_) => None,
+            DatabaseLocation::Profile(name) => Some(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_to_default_profile() {
+        let location = DatabaseLocation::choose(None, None, None, None);
+        assert_eq!(
+            location,
+            DatabaseLocation::Profile(DEFAULT_PROFILE.to_string())
+        );
+    }
+
+    #[test]
+    fn test_flags_take_precedence_over_environment() {
+        let location =
+            DatabaseLocation::choose(None, Some("anna"), Some("/tmp/env.sqlite3"), Some("piotr"));
+        assert_eq!(location, DatabaseLocation::Profile("anna".to_string()));
+
+        let location =
+            DatabaseLocation::choose(Some(Path::new("cli.sqlite3")), Some("anna"), None, None);
+        assert_eq!(
+            location,
+            DatabaseLocation::Path(PathBuf::from("cli.sqlite3"))
+        );
+    }
+
+    #[test]
+    fn test_environment_path_before_environment_profile() {
+        let location =
+            DatabaseLocation::choose(None, None, Some("/tmp/env.sqlite3"), Some("piotr"));
+        assert_eq!(
+            location,
+            DatabaseLocation::Path(PathBuf::from("/tmp/env.sqlite3"))
+        );
+
+        let location = DatabaseLocation::choose(None, None, Some(""), Some("piotr"));
+        assert_eq!(location, DatabaseLocation::Profile("piotr".to_string()));
+    }
+
+    #[test]
+    fn test_legacy_database_is_adopted_once() {
+        let dir = std::env::temp_dir().join("flashcards_test_legacy");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let legacy = dir.join(LEGACY_DATABASE_FILE);
+        let profile = dir.join("profiles").join("default.sqlite3");
+
+        // Without a legacy database the default profile starts empty
+        assert!(!adopt_legacy_database(&legacy, &profile).unwrap());
+        assert!(!profile.exists());
+
+        let conn = crate::database::db::init_database(&legacy).unwrap();
+        crate::database::db::new_deck("Polish", &conn).unwrap();
+        drop(conn);
+
+        assert!(adopt_legacy_database(&legacy, &profile).unwrap());
+        let conn = crate::database::db::init_database(&profile).unwrap();
+        assert_eq!(
+            crate::database::db::get_all_decks(&conn).unwrap(),
+            vec!["Polish"]
+        );
+        crate::database::db::new_deck("German", &conn).unwrap();
+        drop(conn);
+
+        // An existing profile database is never overwritten
+        assert!(!adopt_legacy_database(&legacy, &profile).unwrap());
+        let conn = crate::database::db::init_database(&profile).unwrap();
+        assert_eq!(crate::database::db::get_all_decks(&conn).unwrap().len(), 2);
+        assert!(legacy.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_profile_names_are_validated() {
+        assert!(validate_profile_name("anna_2").is_ok());
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name("../evil").is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Content-addressed store for media files attached to flashcards.
+//! Files live in a directory next to the database, e.g. `default.media/` for
+//! `default.sqlite3`, named after the SHA-256 hash of their content and their
+//! extension. Adding the same file twice stores it once, and a name always
+//! refers to the same content, so decks and backups can carry files by name.
+//! Cards refer to images and audio clips, told apart by their extension.
+
+use crate::error::{FlashcardsError, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Extensions of image files that can be attached to a card
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "webp"];
+/// Extensions of audio files that can be attached to a card
+pub const AUDIO_EXTENSIONS: &[&str] = &["mp3", "ogg", "opus", "wav", "flac", "m4a"];
+
+/// Length of a hex encoded SHA-256 hash
+const HASH_LENGTH: usize = 64;
+
+/// Kind of media file attached to a card
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaKind {
+    Image,
+    Audio,
+}
+
+impl MediaKind {
+    /// Extensions of the files of this kind
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            MediaKind::Image => IMAGE_EXTENSIONS,
+            MediaKind::Audio => AUDIO_EXTENSIONS,
+        }
+    }
+
+    /// Human readable name for messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            MediaKind::Image => "image",
+            MediaKind::Audio => "audio file",
+        }
+    }
+
+    /// True if the file name or path has an extension of this kind
+    pub fn matches(&self, name: &str) -> bool {
+        extension(name).is_some_and(|ext| self.extensions().contains(&ext.as_str()))
+    }
+}
+
+/// Lowercase extension of a file name
+fn extension(name: &str) -> Option<String> {
+    Path::new(name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+}
+
+/// Name of a stored file: the hash of its content and its lowercase extension
+fn content_name(bytes: &[u8], extension: &str) -> String {
+    format!(
+        "{:x}.{}",
+        Sha256::digest(bytes),
+        extension.to_ascii_lowercase()
+    )
+}
+
+/// Checks that a name was produced by the store, so it cannot point outside of it
+pub fn validate_media_name(name: &str) -> Result<()> {
+    let valid = name.split_once('.').is_some_and(|(hash, ext)| {
+        hash.len() == HASH_LENGTH
+            && hash
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+            && !ext.is_empty()
+            && ext
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(FlashcardsError::Validation(format!(
+            "Invalid media file name '{}'",
+            name
+        )))
+    }
+}
+
+/// Content of a media file read from disk, not yet in a store
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaFile {
+    /// Name the file is stored under
+    pub name: String,
+    bytes: Vec<u8>,
+}
+
+/// Directory of media files shared by all cards of a database
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaStore {
+    dir: PathBuf,
+}
+
+impl Default for MediaStore {
+    /// Store in the temporary directory, shared by all in-memory databases
+    fn default() -> Self {
+        Self::new(std::env::temp_dir().join("flashcards-memory.media"))
+    }
+}
+
+impl MediaStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store next to the database file at `db_path`
+    pub fn for_database(db_path: &Path) -> Self {
+        Self::new(db_path.with_extension("media"))
+    }
+
+    /// Store of an open database; in-memory databases use the default store
+    pub fn for_connection(conn: &Connection) -> Self {
+        match conn.path() {
+            Some(path) if !path.is_empty() => Self::for_database(Path::new(path)),
+            _ => Self::default(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Location of a stored file
+    pub fn path(&self, name: &str) -> Result<PathBuf> {
+        validate_media_name(name)?;
+        Ok(self.dir.join(name))
+    }
+
+    /// Stores file content and returns its name
+    pub fn add_bytes(&self, bytes: &[u8], extension: &str) -> Result<String> {
+        let name = content_name(bytes, extension);
+        self.write(&name, bytes)?;
+        Ok(name)
+    }
+
+    /// Writes content under its name unless the store already has it
+    fn write(&self, name: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            std::fs::create_dir_all(&self.dir)?;
+            // Write under a temporary name so a crash never leaves a partial file
+            let temp = self.dir.join(format!("{}.tmp", name));
+            std::fs::write(&temp, bytes)?;
+            std::fs::rename(&temp, &path)?;
+        }
+        Ok(())
+    }
+
+    /// Copies an image or audio file into the store and returns its name
+    pub fn add_file(&self, path: &Path, kind: MediaKind) -> Result<String> {
+        let file = Self::read_file(path, kind)?;
+        self.add(&file)?;
+        Ok(file.name)
+    }
+
+    /// Reads an image or audio file without storing it yet, see `add`
+    pub fn read_file(path: &Path, kind: MediaKind) -> Result<MediaFile> {
+        let name = path.to_string_lossy();
+        if !kind.matches(&name) {
+            return Err(FlashcardsError::Validation(format!(
+                "'{}' is not an {}; supported are {}",
+                name,
+                kind.label(),
+                kind.extensions().join(", ")
+            )));
+        }
+        let bytes = std::fs::read(path)?;
+        Ok(MediaFile {
+            name: content_name(&bytes, &extension(&name).unwrap_or_default()),
+            bytes,
+        })
+    }
+
+    /// Stores a file read with `read_file`
+    pub fn add(&self, file: &MediaFile) -> Result<()> {
+        self.write(&file.name, &file.bytes)
+    }
+
+    /// Reads the files with the given names, base64 encoded, for embedding in JSON
+    ///
+    /// Files missing from the store are left out.
+    pub fn encode_files<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<BTreeMap<String, String>> {
+        let mut files = BTreeMap::new();
+        for name in names {
+            let path = self.path(name)?;
+            if path.exists() {
+                files.insert(name.to_string(), BASE64.encode(std::fs::read(path)?));
+            }
+        }
+        Ok(files)
+    }
+
+    /// Stores files produced by `encode_files`
+    ///
+    /// Fails if the content of a file does not match its name; such a file
+    /// is not written.
+    pub fn decode_files(&self, files: &BTreeMap<String, String>) -> Result<()> {
+        for (name, data) in files {
+            validate_media_name(name)?;
+            let bytes = BASE64
+                .decode(data)
+                .map_err(|e| FlashcardsError::Parse(format!("Media file '{}': {}", name, e)))?;
+            if content_name(&bytes, &extension(name).unwrap_or_default()) != *name {
+                return Err(FlashcardsError::Parse(format!(
+                    "Media file '{}' is damaged",
+                    name
+                )));
+            }
+            self.write(name, &bytes)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> MediaStore {
+        let dir = std::env::temp_dir().join(format!("flashcards_test_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        MediaStore::new(dir)
+    }
+
+    #[test]
+    fn test_same_content_is_stored_once() {
+        let store = temp_store("media_once");
+        let first = store.add_bytes(b"image", "PNG").unwrap();
+        let second = store.add_bytes(b"image", "png").unwrap();
+
+        assert_eq!(first, second);
+        assert!(first.ends_with(".png"));
+        assert_eq!(std::fs::read_dir(store.dir()).unwrap().count(), 1);
+        let _ = std::fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn test_files_must_match_their_kind() {
+        let store = temp_store("media_kind");
+        let clip = std::env::temp_dir().join("flashcards_test_kot.OGG");
+        std::fs::write(&clip, b"miau").unwrap();
+
+        let name = store.add_file(&clip, MediaKind::Audio).unwrap();
+        assert!(name.ends_with(".ogg"));
+        assert!(matches!(
+            store.add_file(&clip, MediaKind::Image),
+            Err(FlashcardsError::Validation(_))
+        ));
+        let _ = std::fs::remove_file(&clip);
+        let _ = std::fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn test_encoded_files_roundtrip() {
+        let source = temp_store("media_source");
+        let target = temp_store("media_target");
+        let name = source.add_bytes(b"map of France", "jpg").unwrap();
+
+        let mut files = source.encode_files([name.as_str()]).unwrap();
+        target.decode_files(&files).unwrap();
+        assert_eq!(
+            std::fs::read(target.path(&name).unwrap()).unwrap(),
+            b"map of France"
+        );
+
+        files.insert(name.clone(), BASE64.encode(b"something else"));
+        assert!(matches!(
+            target.decode_files(&files),
+            Err(FlashcardsError::Parse(_))
+        ));
+        // The damaged content is not left behind under its own name
+        assert_eq!(std::fs::read_dir(target.dir()).unwrap().count(), 1);
+        assert!(matches!(
+            target.path("../secret.png"),
+            Err(FlashcardsError::Validation(_))
+        ));
+        let _ = std::fs::remove_dir_all(source.dir());
+        let _ = std::fs::remove_dir_all(target.dir());
+    }
+}
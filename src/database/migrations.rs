@@ -0,0 +1,334 @@
+//! Versioned schema migrations.
+//!
+//! The schema version is stored in SQLite's `PRAGMA user_version`. Each entry in
+//! `MIGRATIONS` upgrades the schema by one version and is applied in its own
+//! transaction together with the version bump, so a failed migration leaves the
+//! database at the previous version. Databases with a version newer than this
+//! binary knows about are refused.
+//!
+//! New schema changes are added by appending a migration - never edit one that
+//! has already been released.
+
+use crate::error::FlashcardsError;
+use rusqlite::{Connection, Result, Transaction};
+
+/// A single schema upgrade
+type Migration = fn(&Transaction) -> Result<()>;
+
+/// Ordered list of migrations; migration `i` upgrades version `i` to `i + 1`
+const MIGRATIONS: &[Migration] = &[
+    initial_schema,  // 1
+    card_directions, // 2
+    daily_limits,    // 3
+    tags,            // 4
+    card_images,     // 5
+    card_audio,      // 6
+];
+
+/// Schema version produced by this build
+pub const SCHEMA_VERSION: i32 = MIGRATIONS.len() as i32;
+
+/// Reads the schema version of a database (0 for new or pre-migration databases)
+pub fn schema_version(conn: &Connection) -> crate::error::Result<i32> {
+    let version = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok(version)
+}
+
+/// Brings the database schema up to `SCHEMA_VERSION`
+///
+/// Fails without touching the database if it was created by a newer version of the app.
+pub fn migrate(conn: &mut Connection) -> crate::error::Result<()> {
+    let version = schema_version(conn)?;
+
+    if version > SCHEMA_VERSION {
+        return Err(FlashcardsError::UnsupportedSchema {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", index as i32 + 1)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+/// Adds a column to a table created by an older version of the app
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<String>>>()?
+        .iter()
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            (),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Version 1: the schema as it existed before versioning was introduced.
+///
+/// Unversioned databases may already contain some or all of these tables, so
+/// every statement is idempotent.
+fn initial_schema(tx: &Transaction) -> Result<()> {
+    // Create decks table
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS decks (
+            name TEXT PRIMARY KEY,
+            scheduler TEXT NOT NULL DEFAULT 'sm2'
+        )",
+        (),
+    )?;
+    add_column_if_missing(tx, "decks", "scheduler", "TEXT NOT NULL DEFAULT 'sm2'")?;
+
+    // Create flashcards table with auto-increment ID
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS flashcards (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            deck_name TEXT NOT NULL,
+            term TEXT NOT NULL,
+            definition TEXT NOT NULL,
+            FOREIGN KEY (deck_name) REFERENCES decks(name),
+            UNIQUE(deck_name, term)
+        )",
+        (),
+    )?;
+
+    // Create review_data table for SM-2 and FSRS scheduling state
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS review_data (
+            flashcard_id INTEGER PRIMARY KEY,
+            easiness_factor REAL NOT NULL DEFAULT 2.5,
+            interval_days INTEGER NOT NULL DEFAULT 0,
+            repetitions INTEGER NOT NULL DEFAULT 0,
+            next_review_date INTEGER NOT NULL,
+            stability REAL NOT NULL DEFAULT 0,
+            difficulty REAL NOT NULL DEFAULT 0,
+            FOREIGN KEY (flashcard_id) REFERENCES flashcards(id) ON DELETE CASCADE
+        )",
+        (),
+    )?;
+    add_column_if_missing(tx, "review_data", "stability", "REAL NOT NULL DEFAULT 0")?;
+    add_column_if_missing(tx, "review_data", "difficulty", "REAL NOT NULL DEFAULT 0")?;
+
+    // Create review_log table with the history of every grade
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS review_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            flashcard_id INTEGER NOT NULL,
+            reviewed_at INTEGER NOT NULL,
+            quality INTEGER NOT NULL,
+            previous_interval_days INTEGER NOT NULL,
+            new_interval_days INTEGER NOT NULL,
+            previous_easiness_factor REAL NOT NULL,
+            new_easiness_factor REAL NOT NULL,
+            time_taken_ms INTEGER NOT NULL,
+            FOREIGN KEY (flashcard_id) REFERENCES flashcards(id) ON DELETE CASCADE
+        )",
+        (),
+    )?;
+
+    // Create app_state table for storing current date
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS app_state (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        (),
+    )?;
+
+    Ok(())
+}
+
+/// Version 2: reverse card directions.
+///
+/// Decks and flashcards get a direction setting (NULL on a flashcard means the
+/// deck setting applies), and review data is keyed by flashcard and direction
+/// so that each direction is scheduled independently. Existing review data and
+/// history belong to the forward direction.
+fn card_directions(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE decks ADD COLUMN directions TEXT NOT NULL DEFAULT 'forward';
+         ALTER TABLE flashcards ADD COLUMN directions TEXT;
+         ALTER TABLE review_log ADD COLUMN direction TEXT NOT NULL DEFAULT 'forward';
+
+         CREATE TABLE review_data_new (
+             flashcard_id INTEGER NOT NULL,
+             direction TEXT NOT NULL DEFAULT 'forward',
+             easiness_factor REAL NOT NULL DEFAULT 2.5,
+             interval_days INTEGER NOT NULL DEFAULT 0,
+             repetitions INTEGER NOT NULL DEFAULT 0,
+             next_review_date INTEGER NOT NULL,
+             stability REAL NOT NULL DEFAULT 0,
+             difficulty REAL NOT NULL DEFAULT 0,
+             PRIMARY KEY (flashcard_id, direction),
+             FOREIGN KEY (flashcard_id) REFERENCES flashcards(id) ON DELETE CASCADE
+         );
+         INSERT INTO review_data_new (flashcard_id, direction, easiness_factor, interval_days,
+                                      repetitions, next_review_date, stability, difficulty)
+             SELECT flashcard_id, 'forward', easiness_factor, interval_days,
+                    repetitions, next_review_date, stability, difficulty
+             FROM review_data;
+         DROP TABLE review_data;
+         ALTER TABLE review_data_new RENAME TO review_data;",
+    )
+}
+
+/// Version 3: per-deck daily limits for new cards and reviews
+fn daily_limits(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE decks ADD COLUMN new_cards_per_day INTEGER NOT NULL DEFAULT 20;
+         ALTER TABLE decks ADD COLUMN reviews_per_day INTEGER NOT NULL DEFAULT 200;",
+    )
+}
+
+/// Version 4: tags shared by flashcards across decks
+fn tags(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE tags (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             name TEXT NOT NULL UNIQUE COLLATE NOCASE
+         );
+         CREATE TABLE flashcard_tags (
+             flashcard_id INTEGER NOT NULL,
+             tag_id INTEGER NOT NULL,
+             PRIMARY KEY (flashcard_id, tag_id),
+             FOREIGN KEY (flashcard_id) REFERENCES flashcards(id) ON DELETE CASCADE,
+             FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+         );
+         CREATE INDEX idx_flashcard_tags_tag ON flashcard_tags(tag_id);",
+    )
+}
+
+/// Version 5: optional images on either side of a card, named as in the media store
+fn card_images(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE flashcards ADD COLUMN term_image TEXT;
+         ALTER TABLE flashcards ADD COLUMN definition_image TEXT;",
+    )
+}
+
+/// Version 6: optional audio clips on either side of a card, named as in the media store
+fn card_audio(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE flashcards ADD COLUMN term_audio TEXT;
+         ALTER TABLE flashcards ADD COLUMN definition_audio TEXT;",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column_names(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .unwrap();
+        stmt.query_map([], |row| row.get::<_, String>(1))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_migrate_new_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(column_names(&conn, "review_log").contains(&"quality".to_string()));
+    }
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        migrate(&mut conn).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (name TEXT PRIMARY KEY);
+             CREATE TABLE review_data (
+                 flashcard_id INTEGER PRIMARY KEY,
+                 easiness_factor REAL NOT NULL DEFAULT 2.5,
+                 interval_days INTEGER NOT NULL DEFAULT 0,
+                 repetitions INTEGER NOT NULL DEFAULT 0,
+                 next_review_date INTEGER NOT NULL
+             );
+             INSERT INTO decks (name) VALUES ('Old Deck');",
+        )
+        .unwrap();
+
+        migrate(&mut conn).unwrap();
+
+        assert!(column_names(&conn, "decks").contains(&"scheduler".to_string()));
+        assert!(column_names(&conn, "review_data").contains(&"direction".to_string()));
+        assert!(column_names(&conn, "review_data").contains(&"stability".to_string()));
+        let name: String = conn
+            .query_row("SELECT name FROM decks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(name, "Old Deck");
+    }
+
+    #[test]
+    fn test_directions_keep_existing_review_data() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        {
+            let tx = conn.transaction().unwrap();
+            initial_schema(&tx).unwrap();
+            tx.pragma_update(None, "user_version", 1).unwrap();
+            tx.commit().unwrap();
+        }
+        conn.execute_batch(
+            "INSERT INTO decks (name) VALUES ('Polish');
+             INSERT INTO flashcards (id, deck_name, term, definition) VALUES (1, 'Polish', 'kot', 'cat');
+             INSERT INTO review_data (flashcard_id, interval_days, next_review_date) VALUES (1, 6, 100);",
+        )
+        .unwrap();
+
+        migrate(&mut conn).unwrap();
+
+        let (direction, interval): (String, i32) = conn
+            .query_row(
+                "SELECT direction, interval_days FROM review_data WHERE flashcard_id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(direction, "forward");
+        assert_eq!(interval, 6);
+    }
+
+    #[test]
+    fn test_refuses_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+
+        assert!(matches!(
+            migrate(&mut conn),
+            Err(FlashcardsError::UnsupportedSchema { .. })
+        ));
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION + 1);
+    }
+}
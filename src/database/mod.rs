@@ -1 +1,4 @@
 pub mod db;
+pub mod location;
+pub mod media;
+pub mod migrations;
@@ -0,0 +1,81 @@
+//! Error type returned by all public library APIs.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum FlashcardsError {
+    /// The SQLite database failed
+    Storage(rusqlite::Error),
+    /// Reading or writing a file failed
+    Io(std::io::Error),
+    /// A file or stored value has an invalid format
+    Parse(String),
+    /// Input was rejected before anything was changed
+    Validation(String),
+    /// A deck, flashcard or other record does not exist
+    NotFound(String),
+    /// The database was created by a newer version of the app
+    UnsupportedSchema { found: i32, supported: i32 },
+    /// Another thread panicked while holding the database connection
+    LockPoisoned,
+}
+
+pub type Result<T> = std::result::Result<T, FlashcardsError>;
+
+impl fmt::Display for FlashcardsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlashcardsError::Storage(e) => write!(f, "Database error: {}", e),
+            FlashcardsError::Io(e) => write!(f, "I/O error: {}", e),
+            FlashcardsError::Parse(message) => write!(f, "Parse error: {}", message),
+            FlashcardsError::Validation(message) => write!(f, "{}", message),
+            FlashcardsError::NotFound(what) => write!(f, "{} does not exist", what),
+            FlashcardsError::UnsupportedSchema { found, supported } => write!(
+                f,
+                "Database schema version {} is newer than the supported version {}; please update the app",
+                found, supported
+            ),
+            FlashcardsError::LockPoisoned => write!(f, "Database connection is unavailable"),
+        }
+    }
+}
+
+impl std::error::Error for FlashcardsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FlashcardsError::Storage(e) => Some(e),
+            FlashcardsError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for FlashcardsError {
+    fn from(e: rusqlite::Error) -> Self {
+        FlashcardsError::Storage(e)
+    }
+}
+
+impl From<std::io::Error> for FlashcardsError {
+    fn from(e: std::io::Error) -> Self {
+        FlashcardsError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for FlashcardsError {
+    fn from(e: serde_json::Error) -> Self {
+        FlashcardsError::Parse(e.to_string())
+    }
+}
+
+impl From<csv::Error> for FlashcardsError {
+    fn from(e: csv::Error) -> Self {
+        FlashcardsError::Parse(e.to_string())
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for FlashcardsError {
+    fn from(_: std::sync::PoisonError<T>) -> Self {
+        FlashcardsError::LockPoisoned
+    }
+}
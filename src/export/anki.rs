@@ -0,0 +1,487 @@
+//! Anki package (.apkg) import.
+//! A package is a zip archive holding a SQLite collection. Notes are mapped to
+//! flashcards using their first field as the term and second field as the
+//! definition; HTML formatting is reduced to plain text. Scheduling of review
+//! cards can optionally be carried over into review data. Images and sounds
+//! referenced by notes are not imported; they are counted so the user can be told.
+//!
+//! Only the legacy collection formats (`collection.anki2` and
+//! `collection.anki21`) can be read. Packages exported by recent Anki versions
+//! contain a compressed `collection.anki21b` instead and must be exported with
+//! "Support older Anki versions" enabled.
+
+use crate::database::db;
+use crate::error::{FlashcardsError, Result};
+use crate::models::tag_filter::validate_tag;
+use crate::models::{CardDirection, Flashcard, ReviewData};
+use rusqlite::{Connection, OpenFlags};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Separator between the fields of a note
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// Card type of cards in review (as opposed to new or learning cards)
+const CARD_TYPE_REVIEW: i64 = 2;
+
+/// Scheduling state of an Anki review card
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnkiSchedule {
+    pub interval_days: i32,
+    pub easiness_factor: f64,
+    pub repetitions: i32,
+    pub next_review_date: SystemTime,
+    /// FSRS memory state, present if the collection used FSRS
+    pub stability: f64,
+    pub difficulty: f64,
+}
+
+/// A note converted to a flashcard
+#[derive(Clone)]
+pub struct AnkiCard {
+    pub flashcard: Flashcard,
+    /// Scheduling of the note's first card, if it has been reviewed
+    pub schedule: Option<AnkiSchedule>,
+}
+
+/// Contents of an Anki package
+pub struct AnkiPackage {
+    /// Name of the deck most cards belong to
+    pub deck_name: String,
+    pub cards: Vec<AnkiCard>,
+    /// Notes with fewer than two non-empty fields
+    pub skipped: usize,
+    /// Notes repeating the term of an earlier note, which is kept instead
+    pub duplicates: usize,
+    /// Image and sound references in the fields of `cards`, left out of the flashcards
+    pub missing_media: usize,
+}
+
+impl AnkiPackage {
+    /// Describes the notes and media left out of the import, one line each
+    pub fn omissions(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.skipped > 0 {
+            lines.push(format!(
+                "{} notes without a term and definition are skipped.",
+                self.skipped
+            ));
+        }
+        if self.duplicates > 0 {
+            lines.push(format!(
+                "{} notes repeating the term of an earlier note are skipped.",
+                self.duplicates
+            ));
+        }
+        if self.missing_media > 0 {
+            lines.push(format!(
+                "{} images and sounds referenced by notes are not imported.",
+                self.missing_media
+            ));
+        }
+        lines
+    }
+}
+
+/// Extracted collection file, deleted when dropped
+struct TempCollection(PathBuf);
+
+impl Drop for TempCollection {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Reads all notes of an Anki package
+pub fn read_apkg(path: &Path) -> Result<AnkiPackage> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?).map_err(zip_error)?;
+
+    let entry = ["collection.anki21", "collection.anki2"]
+        .into_iter()
+        .find(|name| archive.index_for_name(name).is_some());
+    let Some(entry) = entry else {
+        return Err(FlashcardsError::Parse(
+            if archive.index_for_name("collection.anki21b").is_some() {
+                "This package uses the latest Anki format; export it again with \
+                 \"Support older Anki versions\" enabled"
+                    .to_string()
+            } else {
+                "Not an Anki package: no collection found".to_string()
+            },
+        ));
+    };
+
+    let mut bytes = Vec::new();
+    archive
+        .by_name(entry)
+        .map_err(zip_error)?
+        .read_to_end(&mut bytes)?;
+
+    // SQLite can only open collections from disk
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let temp = TempCollection(std::env::temp_dir().join(format!(
+        "flashcards-anki-{}-{}.sqlite3",
+        std::process::id(),
+        nanos
+    )));
+    std::fs::write(&temp.0, bytes)?;
+
+    let conn = Connection::open_with_flags(&temp.0, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    read_collection(&conn, path)
+}
+
+fn zip_error(e: zip::result::ZipError) -> FlashcardsError {
+    match e {
+        zip::result::ZipError::Io(e) => FlashcardsError::Io(e),
+        e => FlashcardsError::Parse(format!("Invalid package: {}", e)),
+    }
+}
+
+fn read_collection(conn: &Connection, path: &Path) -> Result<AnkiPackage> {
+    let (created, decks_json): (i64, String) =
+        conn.query_row("SELECT crt, decks FROM col", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+    let deck_names = parse_deck_names(&decks_json)?;
+
+    // The first card of every note, with its deck and scheduling
+    let mut stmt = conn.prepare(
+        "SELECT n.flds, n.tags, c.did, c.type, c.due, c.ivl, c.factor, c.reps, c.data
+         FROM notes n
+         JOIN cards c ON c.nid = n.id
+         WHERE c.ord = (SELECT MIN(ord) FROM cards WHERE nid = n.id)
+         ORDER BY n.id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(NoteRow {
+                fields: row.get(0)?,
+                tags: row.get(1)?,
+                deck_id: row.get(2)?,
+                card_type: row.get(3)?,
+                due: row.get(4)?,
+                interval: row.get(5)?,
+                factor: row.get(6)?,
+                reps: row.get(7)?,
+                data: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut cards = Vec::new();
+    let mut skipped = 0;
+    let mut duplicates = 0;
+    let mut missing_media = 0;
+    let mut terms = HashSet::new();
+    let mut deck_counts: HashMap<i64, usize> = HashMap::new();
+
+    for row in rows {
+        let raw_fields: Vec<&str> = row.fields.split(FIELD_SEPARATOR).take(2).collect();
+        let fields: Vec<String> = raw_fields
+            .iter()
+            .map(|field| remove_sound_references(&html_to_text(field)))
+            .collect();
+        let (Some(term), Some(definition)) = (fields.first(), fields.get(1)) else {
+            skipped += 1;
+            continue;
+        };
+        if term.is_empty() || definition.is_empty() {
+            skipped += 1;
+            continue;
+        }
+        // Terms are unique within a deck, so a later note would replace the first
+        if !terms.insert(term.clone()) {
+            duplicates += 1;
+            continue;
+        }
+
+        missing_media += raw_fields
+            .iter()
+            .map(|field| count_media_references(field))
+            .sum::<usize>();
+        *deck_counts.entry(row.deck_id).or_default() += 1;
+        cards.push(AnkiCard {
+            flashcard: Flashcard {
+                term: term.clone(),
+                definition: definition.clone(),
+                // Anki allows characters our tag expressions use as operators
+                tags: row
+                    .tags
+                    .split_whitespace()
+                    .filter(|tag| validate_tag(tag).is_ok())
+                    .map(str::to_string)
+                    .collect(),
+                ..Flashcard::default()
+            },
+            schedule: row.schedule(created),
+        });
+    }
+
+    let deck_name = deck_counts
+        .into_iter()
+        .max_by_key(|(id, count)| (*count, -id))
+        .and_then(|(id, _)| deck_names.get(&id).cloned())
+        .or_else(|| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "Anki".to_string());
+
+    Ok(AnkiPackage {
+        deck_name,
+        cards,
+        skipped,
+        duplicates,
+        missing_media,
+    })
+}
+
+/// A note joined with its first card
+struct NoteRow {
+    fields: String,
+    tags: String,
+    deck_id: i64,
+    card_type: i64,
+    due: i64,
+    interval: i64,
+    factor: i64,
+    reps: i64,
+    data: String,
+}
+
+impl NoteRow {
+    /// Scheduling of review cards; due dates of review cards are days since collection creation
+    fn schedule(&self, collection_created: i64) -> Option<AnkiSchedule> {
+        if self.card_type != CARD_TYPE_REVIEW {
+            return None;
+        }
+
+        let due_secs = collection_created + self.due * 24 * 60 * 60;
+        let memory_state: serde_json::Value = serde_json::from_str(&self.data).unwrap_or_default();
+
+        Some(AnkiSchedule {
+            interval_days: self.interval.max(0) as i32,
+            easiness_factor: (self.factor as f64 / 1000.0).max(1.3),
+            repetitions: self.reps.max(0) as i32,
+            next_review_date: SystemTime::UNIX_EPOCH + Duration::from_secs(due_secs.max(0) as u64),
+            stability: memory_state["s"].as_f64().unwrap_or(0.0),
+            difficulty: memory_state["d"].as_f64().unwrap_or(0.0),
+        })
+    }
+}
+
+/// Maps deck IDs to names from the `decks` JSON of the collection
+fn parse_deck_names(json: &str) -> Result<HashMap<i64, String>> {
+    let decks: HashMap<String, serde_json::Value> = serde_json::from_str(json)?;
+    Ok(decks
+        .into_iter()
+        .filter_map(|(id, deck)| {
+            let name = deck["name"].as_str()?.to_string();
+            Some((id.parse().ok()?, name))
+        })
+        .collect())
+}
+
+/// Counts the `<img>` tags and `[sound:...]` references of a field
+fn count_media_references(field: &str) -> usize {
+    let field = field.to_ascii_lowercase();
+    field.matches("<img").count() + field.matches("[sound:").count()
+}
+
+/// Removes Anki's `[sound:file.mp3]` references from text
+fn remove_sound_references(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("[sound:") {
+        let Some(end) = rest[start..].find(']') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result.trim().to_string()
+}
+
+/// Reduces an HTML field to plain text
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        if tag.starts_with("br") || tag == "/div" || tag == "/p" {
+            text.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+/// Creates a deck from an Anki package, optionally keeping review progress
+pub fn import_apkg_package(
+    package: &AnkiPackage,
+    deck_name: &str,
+    keep_scheduling: bool,
+    conn: &Connection,
+) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    db::new_deck(deck_name, &tx)?;
+
+    for card in &package.cards {
+        let id = db::add_flashcard(
+            deck_name,
+            &card.flashcard.term,
+            &card.flashcard.definition,
+            &tx,
+        )?;
+        if !card.flashcard.tags.is_empty() {
+            db::set_flashcard_tags(id, &card.flashcard.tags, &tx)?;
+        }
+
+        if keep_scheduling && let Some(schedule) = &card.schedule {
+            db::update_review_data(
+                &ReviewData {
+                    flashcard_id: id,
+                    direction: CardDirection::Forward,
+                    easiness_factor: schedule.easiness_factor,
+                    interval_days: schedule.interval_days,
+                    repetitions: schedule.repetitions,
+                    next_review_date: schedule.next_review_date,
+                    stability: schedule.stability,
+                    difficulty: schedule.difficulty,
+                },
+                &tx,
+            )?;
+        }
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Package path of one test, unique across concurrent test runs
+    fn temp_package(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "flashcards_test_{}_{}.apkg",
+            name,
+            std::process::id()
+        ))
+    }
+
+    /// Builds a minimal legacy Anki package with one reviewed and one new note
+    fn write_test_package(path: &Path) {
+        // Next to the package, so tests building packages in parallel do not share it
+        let collection = path.with_extension("anki2");
+        let _ = std::fs::remove_file(&collection);
+        {
+            let conn = Connection::open(&collection).unwrap();
+            conn.execute_batch(
+                r#"CREATE TABLE col (crt INTEGER, decks TEXT);
+                   CREATE TABLE notes (id INTEGER, tags TEXT, flds TEXT);
+                   CREATE TABLE cards (id INTEGER, nid INTEGER, did INTEGER, ord INTEGER,
+                       type INTEGER, due INTEGER, ivl INTEGER, factor INTEGER, reps INTEGER,
+                       data TEXT);
+                   INSERT INTO col VALUES (1700000000,
+                       '{"1": {"name": "Default"}, "42": {"name": "Polish::Animals"}}');
+                   INSERT INTO notes VALUES (1, ' animals ', 'kot' || char(31) || 'cat<br>&amp; kitten');
+                   INSERT INTO notes VALUES (2, '',
+                       '<b>pies</b><img src="dog.jpg">' || char(31) || 'dog [sound:pies.mp3]');
+                   INSERT INTO notes VALUES (3, '', 'only one field');
+                   INSERT INTO notes VALUES (4, '', 'kot' || char(31) || 'tomcat');
+                   INSERT INTO cards VALUES (10, 1, 42, 0, 2, 10, 7, 2300, 5, '');
+                   INSERT INTO cards VALUES (11, 1, 42, 1, 0, 0, 0, 0, 0, '');
+                   INSERT INTO cards VALUES (20, 2, 42, 0, 0, 1, 0, 0, 0, '');
+                   INSERT INTO cards VALUES (30, 3, 1, 0, 0, 2, 0, 0, 0, '');
+                   INSERT INTO cards VALUES (40, 4, 42, 0, 2, 20, 30, 2500, 8, '');"#,
+            )
+            .unwrap();
+        }
+
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        zip.start_file("collection.anki2", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(&std::fs::read(&collection).unwrap()).unwrap();
+        zip.finish().unwrap();
+        let _ = std::fs::remove_file(&collection);
+    }
+
+    #[test]
+    fn test_read_apkg() {
+        let path = temp_package("read");
+        write_test_package(&path);
+        let package = read_apkg(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(package.deck_name, "Polish::Animals");
+        assert_eq!(package.skipped, 1);
+        assert_eq!(package.duplicates, 1);
+        assert_eq!(package.missing_media, 2);
+        assert_eq!(package.cards.len(), 2);
+        assert_eq!(package.cards[0].flashcard.definition, "cat\n& kitten");
+        assert_eq!(package.cards[0].flashcard.tags, vec!["animals"]);
+        assert_eq!(package.cards[1].flashcard.term, "pies");
+        assert_eq!(package.cards[1].flashcard.definition, "dog");
+        assert!(package.cards[1].schedule.is_none());
+
+        let schedule = package.cards[0].schedule.as_ref().unwrap();
+        assert_eq!(schedule.interval_days, 7);
+        assert_eq!(schedule.easiness_factor, 2.3);
+        assert_eq!(
+            schedule.next_review_date,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1700000000 + 10 * 86400)
+        );
+    }
+
+    #[test]
+    fn test_import_keeps_scheduling() {
+        let path = temp_package("import");
+        write_test_package(&path);
+        let package = read_apkg(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let conn = db::init_in_memory_database().unwrap();
+        import_apkg_package(&package, "Animals", true, &conn).unwrap();
+
+        let cards = db::get_flashcards_for_deck("Animals", &conn).unwrap();
+        assert_eq!(cards.len(), 2);
+        let due: Vec<_> = db::get_flashcards_due_for_review("Animals", &conn)
+            .unwrap()
+            .into_iter()
+            .map(|(_, card, review)| (card.term, review.interval_days))
+            .collect();
+        assert!(due.contains(&("kot".to_string(), 7)));
+    }
+
+    #[test]
+    fn test_rejects_non_package() {
+        let path = temp_package("invalid");
+        std::fs::write(&path, "not a zip").unwrap();
+        let result = read_apkg(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert!(matches!(result, Err(FlashcardsError::Parse(_))));
+    }
+}
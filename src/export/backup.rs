@@ -0,0 +1,486 @@
+//! Full backup and restore of a database.
+//! A backup is a JSON file with every row of every table, including IDs, review
+//! data, review history and settings, so restoring it reproduces the database
+//! exactly. Timestamps are kept as stored (seconds since the Unix epoch).
+//! Backups written before card directions existed restore as forward only.
+//! Card images and audio are embedded base64 encoded and restored into the media store.
+
+use crate::database::db;
+use crate::database::media::MediaStore;
+use crate::error::{FlashcardsError, Result};
+use crate::models::limits::{DEFAULT_NEW_CARDS_PER_DAY, DEFAULT_REVIEWS_PER_DAY};
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Version of the backup format written by this build
+pub const BACKUP_VERSION: u32 = 6;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BackupDeck {
+    pub name: String,
+    pub scheduler: String,
+    #[serde(default = "forward")]
+    pub directions: String,
+    #[serde(default = "default_new_cards_per_day")]
+    pub new_cards_per_day: u32,
+    #[serde(default = "default_reviews_per_day")]
+    pub reviews_per_day: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BackupFlashcard {
+    pub id: i64,
+    pub deck_name: String,
+    pub term: String,
+    pub definition: String,
+    #[serde(default)]
+    pub directions: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub term_image: Option<String>,
+    #[serde(default)]
+    pub definition_image: Option<String>,
+    #[serde(default)]
+    pub term_audio: Option<String>,
+    #[serde(default)]
+    pub definition_audio: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BackupReviewData {
+    pub flashcard_id: i64,
+    #[serde(default = "forward")]
+    pub direction: String,
+    pub easiness_factor: f64,
+    pub interval_days: i32,
+    pub repetitions: i32,
+    pub next_review_date: i64,
+    pub stability: f64,
+    pub difficulty: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BackupReviewLogEntry {
+    pub id: i64,
+    pub flashcard_id: i64,
+    #[serde(default = "forward")]
+    pub direction: String,
+    pub reviewed_at: i64,
+    pub quality: u8,
+    pub previous_interval_days: i32,
+    pub new_interval_days: i32,
+    pub previous_easiness_factor: f64,
+    pub new_easiness_factor: f64,
+    pub time_taken_ms: i64,
+}
+
+fn forward() -> String {
+    "forward".to_string()
+}
+
+fn default_new_cards_per_day() -> u32 {
+    DEFAULT_NEW_CARDS_PER_DAY
+}
+
+fn default_reviews_per_day() -> u32 {
+    DEFAULT_REVIEWS_PER_DAY
+}
+
+/// Contents of the whole database
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    /// When the backup was made, in seconds since the Unix epoch
+    pub created_at: i64,
+    pub decks: Vec<BackupDeck>,
+    pub flashcards: Vec<BackupFlashcard>,
+    pub review_data: Vec<BackupReviewData>,
+    pub review_log: Vec<BackupReviewLogEntry>,
+    /// Settings such as the clock mode and the simulated date
+    pub app_state: BTreeMap<String, String>,
+    /// Base64 encoded card images and audio clips by name
+    #[serde(default)]
+    pub media: BTreeMap<String, String>,
+}
+
+/// Reads the whole database into a backup
+pub fn create_backup(conn: &Connection) -> Result<Backup> {
+    let decks = conn
+        .prepare(
+            "SELECT name, scheduler, directions, new_cards_per_day, reviews_per_day
+             FROM decks ORDER BY name",
+        )?
+        .query_map([], |row| {
+            Ok(BackupDeck {
+                name: row.get(0)?,
+                scheduler: row.get(1)?,
+                directions: row.get(2)?,
+                new_cards_per_day: row.get(3)?,
+                reviews_per_day: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    let tag_rows = conn
+        .prepare(
+            "SELECT ft.flashcard_id, t.name FROM flashcard_tags ft
+             JOIN tags t ON t.id = ft.tag_id
+             ORDER BY ft.flashcard_id, t.name",
+        )?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<(i64, String)>>>()?;
+    for (flashcard_id, tag) in tag_rows {
+        tags.entry(flashcard_id).or_default().push(tag);
+    }
+
+    let flashcards = conn
+        .prepare(
+            "SELECT id, deck_name, term, definition, directions, term_image, definition_image,
+                    term_audio, definition_audio
+             FROM flashcards ORDER BY id",
+        )?
+        .query_map([], |row| {
+            let id = row.get(0)?;
+            Ok(BackupFlashcard {
+                id,
+                deck_name: row.get(1)?,
+                term: row.get(2)?,
+                definition: row.get(3)?,
+                directions: row.get(4)?,
+                tags: tags.remove(&id).unwrap_or_default(),
+                term_image: row.get(5)?,
+                definition_image: row.get(6)?,
+                term_audio: row.get(7)?,
+                definition_audio: row.get(8)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let review_data = conn
+        .prepare(
+            "SELECT flashcard_id, easiness_factor, interval_days, repetitions, next_review_date,
+                    stability, difficulty, direction
+             FROM review_data ORDER BY flashcard_id, direction",
+        )?
+        .query_map([], |row| {
+            Ok(BackupReviewData {
+                flashcard_id: row.get(0)?,
+                easiness_factor: row.get(1)?,
+                interval_days: row.get(2)?,
+                repetitions: row.get(3)?,
+                next_review_date: row.get(4)?,
+                stability: row.get(5)?,
+                difficulty: row.get(6)?,
+                direction: row.get(7)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let review_log = conn
+        .prepare(
+            "SELECT id, flashcard_id, reviewed_at, quality, previous_interval_days, new_interval_days,
+                    previous_easiness_factor, new_easiness_factor, time_taken_ms, direction
+             FROM review_log ORDER BY id",
+        )?
+        .query_map([], |row| {
+            Ok(BackupReviewLogEntry {
+                id: row.get(0)?,
+                flashcard_id: row.get(1)?,
+                reviewed_at: row.get(2)?,
+                quality: row.get(3)?,
+                previous_interval_days: row.get(4)?,
+                new_interval_days: row.get(5)?,
+                previous_easiness_factor: row.get(6)?,
+                new_easiness_factor: row.get(7)?,
+                time_taken_ms: row.get(8)?,
+                direction: row.get(9)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let app_state = conn
+        .prepare("SELECT key, value FROM app_state")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<BTreeMap<_, _>>>()?;
+
+    let media = MediaStore::for_connection(conn).encode_files(
+        flashcards
+            .iter()
+            .flat_map(|card| {
+                [
+                    &card.term_image,
+                    &card.definition_image,
+                    &card.term_audio,
+                    &card.definition_audio,
+                ]
+            })
+            .flatten()
+            .map(String::as_str),
+    )?;
+
+    let created_at = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    Ok(Backup {
+        version: BACKUP_VERSION,
+        created_at,
+        decks,
+        flashcards,
+        review_data,
+        review_log,
+        app_state,
+        media,
+    })
+}
+
+/// Replaces all data in the database with the contents of a backup
+///
+/// Runs in a single transaction, so the database is unchanged if the backup
+/// cannot be restored.
+pub fn restore_backup(backup: &Backup, conn: &Connection) -> Result<()> {
+    if backup.version > BACKUP_VERSION {
+        return Err(FlashcardsError::Parse(format!(
+            "Backup format version {} is newer than the supported version {}; please update the app",
+            backup.version, BACKUP_VERSION
+        )));
+    }
+
+    // Stored files are named by their content, so adding them cannot harm a failed restore
+    MediaStore::for_connection(conn).decode_files(&backup.media)?;
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute_batch(
+        "DELETE FROM review_log;
+         DELETE FROM review_data;
+         DELETE FROM flashcard_tags;
+         DELETE FROM tags;
+         DELETE FROM flashcards;
+         DELETE FROM decks;
+         DELETE FROM app_state;",
+    )?;
+
+    for deck in &backup.decks {
+        tx.execute(
+            "INSERT INTO decks (name, scheduler, directions, new_cards_per_day, reviews_per_day)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                deck.name,
+                deck.scheduler,
+                deck.directions,
+                deck.new_cards_per_day,
+                deck.reviews_per_day
+            ],
+        )?;
+    }
+    for card in &backup.flashcards {
+        tx.execute(
+            "INSERT INTO flashcards (id, deck_name, term, definition, directions)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                card.id,
+                card.deck_name,
+                card.term,
+                card.definition,
+                card.directions
+            ],
+        )?;
+        if !card.tags.is_empty() {
+            db::set_flashcard_tags(card.id, &card.tags, &tx)?;
+        }
+        if card.term_image.is_some() || card.definition_image.is_some() {
+            db::set_flashcard_images(
+                card.id,
+                card.term_image.as_deref(),
+                card.definition_image.as_deref(),
+                &tx,
+            )?;
+        }
+        if card.term_audio.is_some() || card.definition_audio.is_some() {
+            db::set_flashcard_audio(
+                card.id,
+                card.term_audio.as_deref(),
+                card.definition_audio.as_deref(),
+                &tx,
+            )?;
+        }
+    }
+    for review in &backup.review_data {
+        tx.execute(
+            "INSERT INTO review_data (flashcard_id, easiness_factor, interval_days, repetitions,
+                                      next_review_date, stability, difficulty, direction)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                review.flashcard_id,
+                review.easiness_factor,
+                review.interval_days,
+                review.repetitions,
+                review.next_review_date,
+                review.stability,
+                review.difficulty,
+                review.direction
+            ],
+        )?;
+    }
+    for entry in &backup.review_log {
+        tx.execute(
+            "INSERT INTO review_log (id, flashcard_id, reviewed_at, quality, previous_interval_days,
+                                     new_interval_days, previous_easiness_factor, new_easiness_factor,
+                                     time_taken_ms, direction)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                entry.id,
+                entry.flashcard_id,
+                entry.reviewed_at,
+                entry.quality,
+                entry.previous_interval_days,
+                entry.new_interval_days,
+                entry.previous_easiness_factor,
+                entry.new_easiness_factor,
+                entry.time_taken_ms,
+                entry.direction
+            ],
+        )?;
+    }
+    for (key, value) in &backup.app_state {
+        tx.execute(
+            "INSERT INTO app_state (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Writes a backup of the database to a JSON file
+pub fn backup_to_path(conn: &Connection, path: &Path) -> Result<Backup> {
+    let backup = create_backup(conn)?;
+    serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), &backup)?;
+    Ok(backup)
+}
+
+/// Reads a backup from a JSON file
+pub fn read_backup(path: &Path) -> Result<Backup> {
+    let backup = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_support::review_log_entry;
+    use crate::models::{CardDirection, ClockMode, ReviewLogEntry, SchedulerKind, sm2};
+    use std::time::Duration;
+
+    fn database_with_progress() -> Connection {
+        let conn = db::init_in_memory_database().unwrap();
+        db::new_deck("Polish", &conn).unwrap();
+        db::set_deck_scheduler("Polish", SchedulerKind::Fsrs, &conn).unwrap();
+        let id = db::add_flashcard("Polish", "kot", "cat", &conn).unwrap();
+        db::add_flashcard("Polish", "pies", "dog", &conn).unwrap();
+        db::set_flashcard_tags(id, &["noun".to_string(), "lesson1".to_string()], &conn).unwrap();
+        let image = MediaStore::for_connection(&conn)
+            .add_bytes(b"cat", "png")
+            .unwrap();
+        db::set_flashcard_images(id, None, Some(&image), &conn).unwrap();
+        let audio = MediaStore::for_connection(&conn)
+            .add_bytes(b"kot", "ogg")
+            .unwrap();
+        db::set_flashcard_audio(id, Some(&audio), None, &conn).unwrap();
+        db::set_clock_mode(ClockMode::Simulated, &conn).unwrap();
+
+        let (_, _, review) = db::get_flashcards_due_for_review("Polish", &conn)
+            .unwrap()
+            .remove(0);
+        let now = db::get_current_date(&conn).unwrap();
+        let next = sm2::calculate_next_review(&review, 4, now);
+        let entry = ReviewLogEntry {
+            time_taken: Duration::from_millis(1234),
+            ..review_log_entry(&review, &next, 4, now)
+        };
+        db::record_review(&next, &entry, &conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_backup_roundtrip() {
+        let conn = database_with_progress();
+        let path = std::env::temp_dir().join(format!(
+            "flashcards_test_backup_{}.json",
+            std::process::id()
+        ));
+        let written = backup_to_path(&conn, &path).unwrap();
+        let read = read_backup(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(read, written);
+
+        let restored = db::init_in_memory_database().unwrap();
+        restore_backup(&read, &restored).unwrap();
+        let mut copy = create_backup(&restored).unwrap();
+        copy.created_at = read.created_at;
+
+        assert_eq!(copy.review_log.len(), 1);
+        assert_eq!(copy.flashcards[0].tags, vec!["lesson1", "noun"]);
+        assert_eq!(copy.media.len(), 2);
+        assert_eq!(copy, read);
+    }
+
+    #[test]
+    fn test_restore_replaces_existing_data() {
+        let backup = create_backup(&database_with_progress()).unwrap();
+
+        let conn = db::init_in_memory_database().unwrap();
+        db::new_deck("Other", &conn).unwrap();
+        db::add_flashcard("Other", "a", "b", &conn).unwrap();
+        restore_backup(&backup, &conn).unwrap();
+
+        assert_eq!(
+            db::get_all_decks(&conn).unwrap(),
+            vec!["Polish".to_string()]
+        );
+        // New cards continue after the restored IDs
+        let id = db::add_flashcard("Polish", "ryba", "fish", &conn).unwrap();
+        assert_eq!(id, 3);
+    }
+
+    #[test]
+    fn test_restores_version_1_backup() {
+        let json = r#"{"version": 1, "created_at": 0,
+            "decks": [{"name": "Polish", "scheduler": "sm2"}],
+            "flashcards": [{"id": 1, "deck_name": "Polish", "term": "kot", "definition": "cat"}],
+            "review_data": [{"flashcard_id": 1, "easiness_factor": 2.5, "interval_days": 3,
+                             "repetitions": 2, "next_review_date": 0, "stability": 0.0,
+                             "difficulty": 0.0}],
+            "review_log": [], "app_state": {}}"#;
+        let backup: Backup = serde_json::from_str(json).unwrap();
+
+        let conn = db::init_in_memory_database().unwrap();
+        restore_backup(&backup, &conn).unwrap();
+        let (_, _, review) = db::get_flashcards_due_for_review("Polish", &conn)
+            .unwrap()
+            .remove(0);
+        assert_eq!(review.direction, CardDirection::Forward);
+        assert_eq!(review.interval_days, 3);
+    }
+
+    #[test]
+    fn test_rejects_newer_backup() {
+        let mut backup = create_backup(&db::init_in_memory_database().unwrap()).unwrap();
+        backup.version = BACKUP_VERSION + 1;
+
+        let conn = db::init_in_memory_database().unwrap();
+        assert!(matches!(
+            restore_backup(&backup, &conn),
+            Err(FlashcardsError::Parse(_))
+        ));
+    }
+}
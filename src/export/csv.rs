@@ -0,0 +1,329 @@
+//! CSV/TSV import/export module for flashcard decks.
+//! The delimiter, quoting, header row and the columns holding the term,
+//! definition and tags are configurable, so spreadsheets can be used as-is.
+
+use crate::error::{FlashcardsError, Result};
+use crate::models::tag_filter::validate_tag;
+use crate::models::{Deck, Flashcard};
+use csv::{QuoteStyle, ReaderBuilder, WriterBuilder};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Which columns hold the parts of a flashcard (zero-based)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnMapping {
+    pub term: usize,
+    pub definition: usize,
+    /// Column with whitespace-separated tags
+    pub tags: Option<usize>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            term: 0,
+            definition: 1,
+            tags: None,
+        }
+    }
+}
+
+/// Format of a delimited text file
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    /// Whether fields may be enclosed in double quotes
+    pub quoting: bool,
+    /// Whether the first row holds column names instead of a flashcard
+    pub has_header: bool,
+    pub columns: ColumnMapping,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quoting: true,
+            has_header: true,
+            columns: ColumnMapping::default(),
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Tab-separated values, as exported by most spreadsheet applications
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: b'\t',
+            ..Self::default()
+        }
+    }
+
+    /// Picks comma or tab as delimiter based on the file extension
+    pub fn for_path(path: &Path) -> Self {
+        let is_tsv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("tsv") || ext.eq_ignore_ascii_case("tab"));
+        if is_tsv { Self::tsv() } else { Self::default() }
+    }
+}
+
+/// Returns true for file extensions handled as delimited text
+pub fn is_delimited_text(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        ["csv", "tsv", "tab", "txt"]
+            .iter()
+            .any(|known| ext.eq_ignore_ascii_case(known))
+    })
+}
+
+/// Raw rows of a file, used to let the user choose the column mapping
+#[derive(Clone, Default)]
+pub struct CsvPreview {
+    /// Column names if the file has a header row
+    pub headers: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+    /// Number of columns in the widest row
+    pub column_count: usize,
+}
+
+impl CsvPreview {
+    /// Index of the column named "tags", if the file has a header row
+    pub fn tags_column(&self) -> Option<usize> {
+        self.headers
+            .as_ref()?
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case("tags"))
+    }
+}
+
+fn reader<R: Read>(source: R, options: &CsvOptions) -> csv::Reader<R> {
+    ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .quoting(options.quoting)
+        .has_headers(options.has_header)
+        .flexible(true)
+        .from_reader(source)
+}
+
+/// Reads up to `limit` rows without applying the column mapping
+pub fn preview_csv<R: Read>(source: R, options: &CsvOptions, limit: usize) -> Result<CsvPreview> {
+    let mut reader = reader(source, options);
+
+    let headers = if options.has_header {
+        Some(reader.headers()?.iter().map(str::to_string).collect())
+    } else {
+        None
+    };
+    let rows = reader
+        .records()
+        .take(limit)
+        .map(|record| record.map(|r| r.iter().map(str::to_string).collect::<Vec<_>>()))
+        .collect::<csv::Result<Vec<_>>>()?;
+
+    let column_count = headers
+        .iter()
+        .chain(rows.iter())
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0);
+
+    Ok(CsvPreview {
+        headers,
+        rows,
+        column_count,
+    })
+}
+
+/// Reads flashcards from delimited text
+///
+/// Blank rows are skipped; rows missing the term or definition or with an
+/// invalid tag are rejected with their line number.
+pub fn parse_csv<R: Read>(source: R, options: &CsvOptions) -> Result<Vec<Flashcard>> {
+    let columns = &options.columns;
+    let mut cards = Vec::new();
+
+    for record in reader(source, options).records() {
+        let record = record?;
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+
+        let field = |index: usize| record.get(index).map(str::trim).unwrap_or("");
+        let term = field(columns.term);
+        let definition = field(columns.definition);
+        if term.is_empty() || definition.is_empty() {
+            return Err(FlashcardsError::Parse(format!(
+                "Line {}: term and definition cannot be empty",
+                line
+            )));
+        }
+
+        let tags: Vec<String> = columns
+            .tags
+            .map(|index| {
+                field(index)
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(error) = tags.iter().find_map(|tag| validate_tag(tag).err()) {
+            return Err(FlashcardsError::Parse(format!("Line {}: {}", line, error)));
+        }
+
+        cards.push(Flashcard {
+            term: term.to_string(),
+            definition: definition.to_string(),
+            tags,
+            ..Flashcard::default()
+        });
+    }
+
+    Ok(cards)
+}
+
+/// Imports a deck from a CSV/TSV file; the deck gets the given name
+pub fn import_csv(path: &Path, deck_name: &str, options: &CsvOptions) -> Result<Deck> {
+    Ok(Deck {
+        name: deck_name.to_string(),
+        flashcards: parse_csv(File::open(path)?, options)?,
+    })
+}
+
+/// Exports a deck to a CSV/TSV file, placing term and definition in their mapped columns
+///
+/// Tags are written to the mapped tags column. Without one, a tags column is
+/// appended if any card of the deck has tags.
+pub fn export_csv_to_path(deck: &Deck, path: &Path, options: &CsvOptions) -> Result<()> {
+    let mut columns = options.columns.clone();
+    if columns.tags.is_none() && deck.flashcards.iter().any(|f| !f.tags.is_empty()) {
+        columns.tags = Some(columns.term.max(columns.definition) + 1);
+    }
+    let width = columns
+        .term
+        .max(columns.definition)
+        .max(columns.tags.unwrap_or(0))
+        + 1;
+
+    let mut writer = WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .quote_style(if options.quoting {
+            QuoteStyle::Necessary
+        } else {
+            QuoteStyle::Never
+        })
+        .from_path(path)?;
+
+    let row = |term: &str, definition: &str, tags: &str| {
+        let mut row = vec![String::new(); width];
+        row[columns.term] = term.to_string();
+        row[columns.definition] = definition.to_string();
+        if let Some(index) = columns.tags {
+            row[index] = tags.to_string();
+        }
+        row
+    };
+
+    if options.has_header {
+        writer.write_record(row("term", "definition", "tags"))?;
+    }
+    for flashcard in &deck.flashcards {
+        writer.write_record(row(
+            &flashcard.term,
+            &flashcard.definition,
+            &flashcard.tags.join(" "),
+        ))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_with_header_and_quotes() {
+        let data = "term,definition\n\"kot, domowy\",cat\npies,\"dog\"\n,\n";
+        let cards = parse_csv(data.as_bytes(), &CsvOptions::default()).unwrap();
+
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].term, "kot, domowy");
+        assert_eq!(cards[1].definition, "dog");
+    }
+
+    #[test]
+    fn test_parse_tsv_with_column_mapping() {
+        let options = CsvOptions {
+            has_header: false,
+            columns: ColumnMapping {
+                term: 2,
+                definition: 0,
+                tags: Some(1),
+            },
+            ..CsvOptions::tsv()
+        };
+        let data = "cat\tanimals pets\tkot\n";
+        let cards = parse_csv(data.as_bytes(), &options).unwrap();
+
+        assert_eq!(cards[0].term, "kot");
+        assert_eq!(cards[0].definition, "cat");
+        assert_eq!(cards[0].tags, vec!["animals", "pets"]);
+    }
+
+    #[test]
+    fn test_parse_reports_line_of_missing_definition() {
+        let data = "term,definition\nkot,cat\npies\n";
+        let result = parse_csv(data.as_bytes(), &CsvOptions::default());
+
+        assert!(matches!(result, Err(FlashcardsError::Parse(m)) if m.starts_with("Line 3")));
+    }
+
+    #[test]
+    fn test_preview_counts_columns() {
+        let data = "a;b\n1;2;3\n";
+        let options = CsvOptions {
+            delimiter: b';',
+            ..CsvOptions::default()
+        };
+        let preview = preview_csv(data.as_bytes(), &options, 10).unwrap();
+
+        assert_eq!(
+            preview.headers,
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(preview.column_count, 3);
+    }
+
+    #[test]
+    fn test_export_and_import_roundtrip() {
+        let deck = Deck {
+            name: "Polish".to_string(),
+            flashcards: vec![Flashcard {
+                term: "kot".to_string(),
+                definition: "cat, \"the\" animal".to_string(),
+                tags: vec!["noun".to_string(), "lesson1".to_string()],
+                ..Flashcard::default()
+            }],
+        };
+        let path = std::env::temp_dir().join(format!(
+            "flashcards_test_roundtrip_{}.csv",
+            std::process::id()
+        ));
+        let mut options = CsvOptions::for_path(&path);
+
+        export_csv_to_path(&deck, &path, &options).unwrap();
+        options.columns.tags = preview_csv(File::open(&path).unwrap(), &options, 1)
+            .unwrap()
+            .tags_column();
+        let imported = import_csv(&path, "Polish", &options).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(imported.flashcards.len(), 1);
+        assert_eq!(imported.flashcards[0].definition, "cat, \"the\" animal");
+        assert_eq!(imported.flashcards[0].tags, vec!["noun", "lesson1"]);
+    }
+}
@@ -1,32 +1,49 @@
 //! JSON import/export module for flashcard decks.
 //! Provides functionality to save and load Deck structures to/from JSON files.
+//! Images and audio clips of the cards are embedded base64 encoded, so a file is
+//! self-contained.
 
+use crate::database::media::MediaStore;
+use crate::error::Result;
 use crate::models::Deck;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{Read, Write};
 
+/// Deck file: the deck with the media files its cards refer to
+#[derive(Serialize, Deserialize)]
+struct DeckFile {
+    #[serde(flatten)]
+    deck: Deck,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    media: BTreeMap<String, String>,
+}
+
 /// Exports a deck to a JSON file at the specified path.
 /// Returns an error if file creation or writing fails.
-pub fn export_json_to_path(deck: &Deck, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let json_string = serde_json::to_string_pretty(deck)?;
+pub fn export_json_to_path(deck: &Deck, path: &str, media: &MediaStore) -> Result<()> {
+    let file = DeckFile {
+        deck: deck.clone(),
+        media: media.encode_files(deck.flashcards.iter().flat_map(|f| f.media_names()))?,
+    };
+    let json_string = serde_json::to_string_pretty(&file)?;
     let mut file = File::create(path)?;
     file.write_all(json_string.as_bytes())?;
     Ok(())
 }
 
-/// Imports a deck from a JSON file.
-/// Prints the deck name upon successful import.
+/// Imports a deck from a JSON file, adding its media files to the store.
 /// Returns an error if the file doesn't exist or contains invalid JSON.
-pub fn import_json(filename: &str) -> Result<Deck, Box<dyn std::error::Error>> {
+pub fn import_json(filename: &str, media: &MediaStore) -> Result<Deck> {
     let mut file = File::open(filename)?;
     let mut contents = String::new();
     file.read_to_string(&mut contents)?;
 
     // Deserialize JSON string into Deck structure
-    let deck: Deck = serde_json::from_str(&contents)?;
-
-    println!("Deck '{}' imported from '{}'", deck.name, filename);
-    Ok(deck)
+    let file: DeckFile = serde_json::from_str(&contents)?;
+    media.decode_files(&file.media)?;
+    Ok(file.deck)
 }
 
 #[cfg(test)]
@@ -35,6 +52,15 @@ mod tests {
     use crate::models::{Deck, Flashcard};
     use std::fs;
 
+    /// Media store of one test, unique across concurrent test runs
+    fn test_store(name: &str) -> MediaStore {
+        MediaStore::new(std::env::temp_dir().join(format!(
+            "flashcards_test_json_{}_{}.media",
+            name,
+            std::process::id()
+        )))
+    }
+
     fn create_test_deck() -> Deck {
         Deck {
             name: "Test Deck".to_string(),
@@ -42,10 +68,14 @@ mod tests {
                 Flashcard {
                     term: "hello".to_string(),
                     definition: "cześć".to_string(),
+                    tags: vec!["greeting".to_string()],
+                    ..Flashcard::default()
                 },
                 Flashcard {
                     term: "goodbye".to_string(),
                     definition: "do widzenia".to_string(),
+                    tags: Vec::new(),
+                    ..Flashcard::default()
                 },
             ],
         }
@@ -56,7 +86,7 @@ mod tests {
         let deck = create_test_deck();
         let test_file = "test_export.json";
 
-        let result = export_json_to_path(&deck, test_file);
+        let result = export_json_to_path(&deck, test_file, &test_store("export"));
         assert!(result.is_ok());
 
         assert!(fs::metadata(test_file).is_ok(), "File should exist");
@@ -79,7 +109,7 @@ mod tests {
         let test_file = "test_import.json";
         fs::write(test_file, json_content).unwrap();
 
-        let result = import_json(test_file);
+        let result = import_json(test_file, &test_store("import"));
         assert!(result.is_ok());
 
         let deck = result.unwrap();
@@ -87,6 +117,7 @@ mod tests {
         assert_eq!(deck.flashcards.len(), 1);
         assert_eq!(deck.flashcards[0].term, "test term");
         assert_eq!(deck.flashcards[0].definition, "test definition");
+        assert!(deck.flashcards[0].tags.is_empty());
 
         let _ = fs::remove_file(test_file);
     }
@@ -96,10 +127,11 @@ mod tests {
         let original_deck = create_test_deck();
         let test_file = "test_roundtrip.json";
 
-        let export_result = export_json_to_path(&original_deck, test_file);
+        let export_result =
+            export_json_to_path(&original_deck, test_file, &test_store("roundtrip"));
         assert!(export_result.is_ok());
 
-        let import_result = import_json(test_file);
+        let import_result = import_json(test_file, &test_store("roundtrip"));
         assert!(import_result.is_ok());
 
         let imported_deck = import_result.unwrap();
@@ -117,14 +149,36 @@ mod tests {
         {
             assert_eq!(orig.term, imp.term);
             assert_eq!(orig.definition, imp.definition);
+            assert_eq!(orig.tags, imp.tags);
         }
 
         let _ = fs::remove_file(test_file);
     }
 
+    #[test]
+    fn test_images_are_embedded() {
+        let source = test_store("source");
+        let target = test_store("target");
+        let _ = fs::remove_dir_all(target.dir());
+        let mut deck = create_test_deck();
+        deck.flashcards[0].term_image = Some(source.add_bytes(b"wave", "png").unwrap());
+        let test_file = "test_images.json";
+
+        export_json_to_path(&deck, test_file, &source).unwrap();
+        let imported = import_json(test_file, &target).unwrap();
+
+        let name = imported.flashcards[0].term_image.as_deref().unwrap();
+        assert_eq!(fs::read(target.path(name).unwrap()).unwrap(), b"wave");
+        assert_eq!(imported.flashcards[1].term_image, None);
+
+        let _ = fs::remove_file(test_file);
+        let _ = fs::remove_dir_all(source.dir());
+        let _ = fs::remove_dir_all(target.dir());
+    }
+
     #[test]
     fn test_import_nonexistent_file() {
-        let result = import_json("nonexistent_file_xyz123.json");
+        let result = import_json("nonexistent_file_xyz123.json", &test_store("nonexistent"));
         assert!(result.is_err());
     }
 
@@ -133,7 +187,7 @@ mod tests {
         let test_file = "test_invalid.json";
         fs::write(test_file, "{ this is not valid json }").unwrap();
 
-        let result = import_json(test_file);
+        let result = import_json(test_file, &test_store("invalid"));
         assert!(result.is_err());
 
         let _ = fs::remove_file(test_file);
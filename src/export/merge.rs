@@ -0,0 +1,375 @@
+//! Merging imported decks into existing ones.
+//! A conflict is an imported card whose term already exists in the target deck
+//! with a different definition; the conflict policy decides what happens to it.
+//! Cards that already exist unchanged are skipped, new cards are added.
+//! Imported tags are added to the tags of matching cards unless the existing
+//! card is kept; imported images and audio replace the existing ones the same way.
+
+use crate::database::db;
+use crate::error::Result;
+use crate::models::{Deck, Flashcard};
+use rusqlite::Connection;
+use std::fmt;
+use std::str::FromStr;
+
+/// What to do with a card whose term already exists in the target deck
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep the existing definition
+    KeepExisting,
+    /// Replace the existing definition, keeping the review progress
+    Overwrite,
+    /// Add the imported card under a numbered term, e.g. "kot (2)"
+    KeepBoth,
+}
+
+/// How conflicts are resolved during a merge
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    #[default]
+    KeepExisting,
+    Overwrite,
+    KeepBoth,
+    /// Decide for every conflict separately
+    Ask,
+}
+
+impl ConflictPolicy {
+    pub const ALL: [ConflictPolicy; 4] = [
+        ConflictPolicy::KeepExisting,
+        ConflictPolicy::Overwrite,
+        ConflictPolicy::KeepBoth,
+        ConflictPolicy::Ask,
+    ];
+
+    /// Human readable name for the UI
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictPolicy::KeepExisting => "Keep existing",
+            ConflictPolicy::Overwrite => "Overwrite definition",
+            ConflictPolicy::KeepBoth => "Keep both",
+            ConflictPolicy::Ask => "Ask for each card",
+        }
+    }
+
+    /// Resolution applied to every conflict, or None if each one is decided separately
+    pub fn resolution(&self) -> Option<Resolution> {
+        match self {
+            ConflictPolicy::KeepExisting => Some(Resolution::KeepExisting),
+            ConflictPolicy::Overwrite => Some(Resolution::Overwrite),
+            ConflictPolicy::KeepBoth => Some(Resolution::KeepBoth),
+            ConflictPolicy::Ask => None,
+        }
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "keep" | "keep-existing" => Ok(ConflictPolicy::KeepExisting),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "both" | "keep-both" => Ok(ConflictPolicy::KeepBoth),
+            "ask" => Ok(ConflictPolicy::Ask),
+            other => Err(format!("Unknown conflict policy '{}'", other)),
+        }
+    }
+}
+
+/// An imported card whose term exists in the target deck with another definition
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    pub flashcard_id: i64,
+    pub term: String,
+    pub existing_definition: String,
+    pub imported_definition: String,
+}
+
+/// Summary of a merge
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportReport {
+    /// New cards, including conflicting cards kept under a numbered term
+    pub added: usize,
+    /// Existing cards whose definition was overwritten
+    pub updated: usize,
+    /// Cards that were already present or whose existing version was kept
+    pub skipped: usize,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} skipped",
+            self.added, self.updated, self.skipped
+        )
+    }
+}
+
+/// Lists the cards of `deck` that conflict with cards in the target deck
+pub fn find_conflicts(deck: &Deck, target_deck: &str, conn: &Connection) -> Result<Vec<Conflict>> {
+    if !db::deck_exists(target_deck, conn)? {
+        return Ok(Vec::new());
+    }
+
+    let mut conflicts = Vec::new();
+    for flashcard in &deck.flashcards {
+        if let Some(id) = db::find_flashcard_id(target_deck, &flashcard.term, conn)? {
+            let (_, existing) = db::get_flashcard(id, conn)?;
+            if existing.definition != flashcard.definition {
+                conflicts.push(Conflict {
+                    flashcard_id: id,
+                    term: flashcard.term.clone(),
+                    existing_definition: existing.definition,
+                    imported_definition: flashcard.definition.clone(),
+                });
+            }
+        }
+    }
+    Ok(conflicts)
+}
+
+/// Merges the cards of `deck` into the target deck, creating it if needed
+///
+/// `ask` is called for every conflict when the policy is `ConflictPolicy::Ask`.
+/// The merge runs in a single transaction.
+pub fn merge_deck(
+    deck: &Deck,
+    target_deck: &str,
+    policy: ConflictPolicy,
+    mut ask: impl FnMut(&Conflict) -> Resolution,
+    conn: &Connection,
+) -> Result<ImportReport> {
+    let tx = conn.unchecked_transaction()?;
+    if !db::deck_exists(target_deck, &tx)? {
+        db::new_deck(target_deck, &tx)?;
+    }
+
+    let mut report = ImportReport::default();
+    for flashcard in &deck.flashcards {
+        let Some(id) = db::find_flashcard_id(target_deck, &flashcard.term, &tx)? else {
+            let id = db::add_flashcard(target_deck, &flashcard.term, &flashcard.definition, &tx)?;
+            add_tags(id, &flashcard.tags, &tx)?;
+            add_media(id, flashcard, &tx)?;
+            report.added += 1;
+            continue;
+        };
+
+        let (_, existing) = db::get_flashcard(id, &tx)?;
+        if existing.definition == flashcard.definition {
+            add_tags(id, &flashcard.tags, &tx)?;
+            add_media(id, flashcard, &tx)?;
+            report.skipped += 1;
+            continue;
+        }
+
+        let conflict = Conflict {
+            flashcard_id: id,
+            term: flashcard.term.clone(),
+            existing_definition: existing.definition,
+            imported_definition: flashcard.definition.clone(),
+        };
+        match policy.resolution().unwrap_or_else(|| ask(&conflict)) {
+            Resolution::KeepExisting => report.skipped += 1,
+            Resolution::Overwrite => {
+                db::update_flashcard(id, &flashcard.term, &flashcard.definition, &tx)?;
+                add_tags(id, &flashcard.tags, &tx)?;
+                add_media(id, flashcard, &tx)?;
+                report.updated += 1;
+            }
+            Resolution::KeepBoth => {
+                let term = numbered_term(target_deck, &flashcard.term, &tx)?;
+                let id = db::add_flashcard(target_deck, &term, &flashcard.definition, &tx)?;
+                add_tags(id, &flashcard.tags, &tx)?;
+                add_media(id, flashcard, &tx)?;
+                report.added += 1;
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
+
+/// Adds tags to the tags a flashcard already has
+fn add_tags(flashcard_id: i64, tags: &[String], conn: &Connection) -> Result<()> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+    let (_, existing) = db::get_flashcard(flashcard_id, conn)?;
+    let all: Vec<String> = existing
+        .tags
+        .into_iter()
+        .chain(tags.iter().cloned())
+        .collect();
+    db::set_flashcard_tags(flashcard_id, &all, conn)
+}
+
+/// Sets the images and audio clips the imported card has, keeping the existing ones otherwise
+fn add_media(flashcard_id: i64, imported: &Flashcard, conn: &Connection) -> Result<()> {
+    if imported.media_names().next().is_none() {
+        return Ok(());
+    }
+    let (_, existing) = db::get_flashcard(flashcard_id, conn)?;
+    let pick = |imported: &Option<String>, existing: &Option<String>| {
+        imported
+            .as_deref()
+            .or(existing.as_deref())
+            .map(str::to_string)
+    };
+    let term_image = pick(&imported.term_image, &existing.term_image);
+    let definition_image = pick(&imported.definition_image, &existing.definition_image);
+    let term_audio = pick(&imported.term_audio, &existing.term_audio);
+    let definition_audio = pick(&imported.definition_audio, &existing.definition_audio);
+    db::set_flashcard_images(
+        flashcard_id,
+        term_image.as_deref(),
+        definition_image.as_deref(),
+        conn,
+    )?;
+    db::set_flashcard_audio(
+        flashcard_id,
+        term_audio.as_deref(),
+        definition_audio.as_deref(),
+        conn,
+    )
+}
+
+/// First free term of the form "term (n)" in a deck
+fn numbered_term(deck_name: &str, term: &str, conn: &Connection) -> Result<String> {
+    let mut n = 2;
+    loop {
+        let candidate = format!("{} ({})", term, n);
+        if db::find_flashcard_id(deck_name, &candidate, conn)?.is_none() {
+            return Ok(candidate);
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn incoming() -> Deck {
+        let card = |term: &str, definition: &str| Flashcard {
+            term: term.to_string(),
+            definition: definition.to_string(),
+            tags: vec!["lesson2".to_string()],
+            ..Flashcard::default()
+        };
+        Deck {
+            name: "Polish".to_string(),
+            flashcards: vec![
+                card("kot", "cat"),
+                card("pies", "hound"),
+                card("ryba", "fish"),
+            ],
+        }
+    }
+
+    fn existing() -> Connection {
+        let conn = db::init_in_memory_database().unwrap();
+        db::new_deck("Polish", &conn).unwrap();
+        db::add_flashcard("Polish", "kot", "cat", &conn).unwrap();
+        db::add_flashcard("Polish", "pies", "dog", &conn).unwrap();
+        conn
+    }
+
+    fn flashcard(term: &str, conn: &Connection) -> Flashcard {
+        let id = db::find_flashcard_id("Polish", term, conn)
+            .unwrap()
+            .unwrap();
+        db::get_flashcard(id, conn).unwrap().1
+    }
+
+    fn definition(term: &str, conn: &Connection) -> String {
+        flashcard(term, conn).definition
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let conflicts = find_conflicts(&incoming(), "Polish", &existing()).unwrap();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].term, "pies");
+        assert_eq!(conflicts[0].existing_definition, "dog");
+    }
+
+    #[test]
+    fn test_merge_policies() {
+        let never_asked = |_: &Conflict| -> Resolution { panic!("policy should not ask") };
+
+        let conn = existing();
+        let report = merge_deck(
+            &incoming(),
+            "Polish",
+            ConflictPolicy::KeepExisting,
+            never_asked,
+            &conn,
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            ImportReport {
+                added: 1,
+                updated: 0,
+                skipped: 2
+            }
+        );
+        assert_eq!(definition("pies", &conn), "dog");
+        assert_eq!(flashcard("kot", &conn).tags, vec!["lesson2"]);
+        assert!(flashcard("pies", &conn).tags.is_empty());
+
+        let conn = existing();
+        let report = merge_deck(
+            &incoming(),
+            "Polish",
+            ConflictPolicy::Overwrite,
+            never_asked,
+            &conn,
+        )
+        .unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(definition("pies", &conn), "hound");
+
+        let conn = existing();
+        merge_deck(
+            &incoming(),
+            "Polish",
+            ConflictPolicy::KeepBoth,
+            never_asked,
+            &conn,
+        )
+        .unwrap();
+        assert_eq!(definition("pies", &conn), "dog");
+        assert_eq!(definition("pies (2)", &conn), "hound");
+    }
+
+    #[test]
+    fn test_merge_asks_for_each_conflict() {
+        let conn = existing();
+        let mut asked = Vec::new();
+        let report = merge_deck(
+            &incoming(),
+            "Polish",
+            ConflictPolicy::Ask,
+            |conflict| {
+                asked.push(conflict.term.clone());
+                Resolution::Overwrite
+            },
+            &conn,
+        )
+        .unwrap();
+
+        assert_eq!(asked, vec!["pies"]);
+        assert_eq!(report.updated, 1);
+    }
+}
@@ -1 +1,5 @@
+pub mod anki;
+pub mod backup;
+pub mod csv;
 pub mod json;
+pub mod merge;
@@ -1,5 +1,8 @@
+pub mod audio;
 pub mod database;
+pub mod error;
 pub mod export;
 pub mod models;
 
+pub use error::{FlashcardsError, Result};
 pub use models::{Deck, DeckSet, Flashcard, LearningCard, LearningSession};
@@ -1,11 +1,33 @@
 mod app;
+mod cli;
 use flashcards_app::*;
 
 use app::MyApp;
-use database::db::{get_all_decks, init_database, load_all_decks, new_deck};
+use clap::Parser;
+use cli::Cli;
+use database::db::{get_all_decks, load_all_decks, new_deck};
+use database::location::{self, DatabaseLocation};
 
 fn main() -> eframe::Result<()> {
-    let conn = init_database().expect("Failed to initialize database");
+    let cli = Cli::parse();
+    let location = DatabaseLocation::from_args(cli.db.as_deref(), cli.profile.as_deref());
+
+    // Subcommands run headless and never open a window
+    if let Some(command) = cli.command {
+        if let Err(e) = cli::run(command, &location) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let conn = match cli::open_database(&location) {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Failed to initialize database: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     if get_all_decks(&conn).unwrap_or_default().is_empty() {
         let _ = new_deck("Polish Vocabulary", &conn);
@@ -19,6 +41,9 @@ fn main() -> eframe::Result<()> {
 
     let deck_set = load_all_decks(&conn).expect("Failed to load decks from database");
 
+    if let Ok(path) = location.path() {
+        println!("Using database '{}'", path.display());
+    }
     println!("Loaded {} decks from database", deck_set.decks.len());
     for deck in &deck_set.decks {
         println!("  - {} ({} cards)", deck.name, deck.flashcards.len());
@@ -27,9 +52,19 @@ fn main() -> eframe::Result<()> {
         viewport: egui::ViewportBuilder::default().with_inner_size([500.0, 700.0]),
         ..Default::default()
     };
+    let title = match location.profile() {
+        Some(profile) if profile != location::DEFAULT_PROFILE => {
+            format!("Flashcards App ({})", profile)
+        }
+        _ => "Flashcards App".to_string(),
+    };
     eframe::run_native(
-        "Flashcards App",
+        &title,
         options,
-        Box::new(|_cc| Ok(Box::new(MyApp::new_with_deckset(deck_set, conn)))),
+        Box::new(|cc| {
+            // Card images are loaded from the media store by file:// URI
+            egui_extras::install_image_loaders(&cc.egui_ctx);
+            Ok(Box::new(MyApp::new_with_deckset(deck_set, conn)))
+        }),
     )
 }
@@ -0,0 +1,350 @@
+//! Checking typed answers against the expected side of a card.
+//! Answers are compared after normalization (whitespace, and optionally case
+//! and diacritics), a few typos are tolerated, and several accepted answers can
+//! be given separated by ";". The result suggests an SM-2 quality grade.
+
+use std::fmt;
+use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Separator between accepted answers, e.g. "cat; kitty"
+pub const ANSWER_SEPARATOR: char = ';';
+
+/// How strictly typed answers are compared
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnswerOptions {
+    pub ignore_case: bool,
+    /// Treat "żółw" and "zolw" as the same answer
+    pub ignore_diacritics: bool,
+    /// Edit distance still counted as correct
+    pub typo_tolerance: usize,
+}
+
+impl Default for AnswerOptions {
+    fn default() -> Self {
+        Self {
+            ignore_case: true,
+            ignore_diacritics: false,
+            typo_tolerance: 1,
+        }
+    }
+}
+
+/// How the answer side is recalled in a learning session
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StudyMode {
+    /// Reveal the answer and grade yourself
+    #[default]
+    SelfGraded,
+    /// Type the answer and have it checked
+    Typed,
+    /// Pick the answer among answers of other cards
+    MultipleChoice,
+}
+
+impl StudyMode {
+    pub const ALL: [StudyMode; 3] = [
+        StudyMode::SelfGraded,
+        StudyMode::Typed,
+        StudyMode::MultipleChoice,
+    ];
+
+    /// Identifier stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StudyMode::SelfGraded => "self",
+            StudyMode::Typed => "typed",
+            StudyMode::MultipleChoice => "choice",
+        }
+    }
+
+    /// Human readable name for the UI
+    pub fn label(&self) -> &'static str {
+        match self {
+            StudyMode::SelfGraded => "Self-graded",
+            StudyMode::Typed => "Type the answer",
+            StudyMode::MultipleChoice => "Multiple choice",
+        }
+    }
+}
+
+impl fmt::Display for StudyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for StudyMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "self" | "self-graded" => Ok(StudyMode::SelfGraded),
+            "typed" => Ok(StudyMode::Typed),
+            "choice" | "multiple-choice" => Ok(StudyMode::MultipleChoice),
+            other => Err(format!("Unknown study mode '{}'", other)),
+        }
+    }
+}
+
+/// Kind of a segment in the difference between typed and expected answer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Typed correctly
+    Same,
+    /// Expected but not typed
+    Missing,
+    /// Typed but not expected
+    Extra,
+}
+
+/// Run of characters of the same kind
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffSegment {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// Result of comparing a typed answer with a card
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnswerCheck {
+    /// Accepted answer closest to what was typed
+    pub expected: String,
+    /// Edit distance between the normalized answers
+    pub distance: usize,
+    /// Character differences against `expected`
+    pub diff: Vec<DiffSegment>,
+    /// Quality grade (0-5) suggested for the answer
+    pub suggested_quality: u8,
+}
+
+impl AnswerCheck {
+    /// True if the suggested grade counts the card as recalled
+    pub fn is_correct(&self) -> bool {
+        self.suggested_quality >= 3
+    }
+}
+
+/// Splits the answer side of a card into its accepted answers
+pub fn accepted_answers(expected: &str) -> Vec<&str> {
+    let answers: Vec<&str> = expected
+        .split(ANSWER_SEPARATOR)
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .collect();
+    if answers.is_empty() {
+        vec![expected.trim()]
+    } else {
+        answers
+    }
+}
+
+/// Normalizes an answer for comparison
+///
+/// Whitespace is always collapsed; case and diacritics are removed if the
+/// options say so. Letters such as "ł" that have no decomposition are mapped
+/// to their base letter as well.
+pub fn normalize(text: &str, options: &AnswerOptions) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut normalized: String = if options.ignore_diacritics {
+        collapsed
+            .nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .map(base_letter)
+            .collect()
+    } else {
+        collapsed.nfc().collect()
+    };
+    if options.ignore_case {
+        normalized = normalized.to_lowercase();
+    }
+    normalized
+}
+
+/// Base letter of letters whose diacritic is not a combining mark
+fn base_letter(c: char) -> char {
+    match c {
+        'ł' => 'l',
+        'Ł' => 'L',
+        'ø' => 'o',
+        'Ø' => 'O',
+        'đ' => 'd',
+        'Đ' => 'D',
+        'ħ' => 'h',
+        'Ħ' => 'H',
+        other => other,
+    }
+}
+
+/// Number of single-character insertions, deletions and substitutions
+/// needed to turn `a` into `b`
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Compares a typed answer with the answer side of a card
+///
+/// The closest accepted answer is used. The suggested grade is 5 for an exact
+/// match, 4 if it only matches after ignoring case or diacritics, 3 within the
+/// typo tolerance, 2 if at most a third of the answer is wrong, 1 otherwise and
+/// 0 for an empty answer.
+pub fn check_answer(typed: &str, expected: &str, options: &AnswerOptions) -> AnswerCheck {
+    let typed_normalized = normalize(typed, options);
+
+    let (expected, distance) = accepted_answers(expected)
+        .into_iter()
+        .map(|answer| {
+            let distance = levenshtein(&typed_normalized, &normalize(answer, options));
+            (answer, distance)
+        })
+        .min_by_key(|(_, distance)| *distance)
+        .unwrap_or((expected, usize::MAX));
+
+    let exact = typed.split_whitespace().eq(expected.split_whitespace());
+    let length = expected.chars().count();
+    let suggested_quality = if typed_normalized.is_empty() {
+        0
+    } else if distance == 0 {
+        if exact { 5 } else { 4 }
+    } else if distance <= options.typo_tolerance {
+        3
+    } else if distance * 3 <= length {
+        2
+    } else {
+        1
+    };
+
+    AnswerCheck {
+        expected: expected.to_string(),
+        distance,
+        diff: diff(typed.trim(), expected, options),
+        suggested_quality,
+    }
+}
+
+/// Character-level difference between typed and expected text, based on their
+/// longest common subsequence
+fn diff(typed: &str, expected: &str, options: &AnswerOptions) -> Vec<DiffSegment> {
+    let typed: Vec<char> = typed.chars().collect();
+    let expected: Vec<char> = expected.chars().collect();
+    let same = |a: char, b: char| {
+        a == b || normalize(&a.to_string(), options) == normalize(&b.to_string(), options)
+    };
+
+    // lcs[i][j]: length of the common subsequence of typed[i..] and expected[j..]
+    let mut lcs = vec![vec![0usize; expected.len() + 1]; typed.len() + 1];
+    for i in (0..typed.len()).rev() {
+        for j in (0..expected.len()).rev() {
+            lcs[i][j] = if same(typed[i], expected[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut segments: Vec<DiffSegment> = Vec::new();
+    let mut push = |kind: DiffKind, c: char| match segments.last_mut() {
+        Some(last) if last.kind == kind => last.text.push(c),
+        _ => segments.push(DiffSegment {
+            kind,
+            text: c.to_string(),
+        }),
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < typed.len() || j < expected.len() {
+        if i < typed.len() && j < expected.len() && same(typed[i], expected[j]) {
+            push(DiffKind::Same, expected[j]);
+            i += 1;
+            j += 1;
+        } else if j < expected.len() && (i == typed.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            push(DiffKind::Missing, expected[j]);
+            j += 1;
+        } else {
+            push(DiffKind::Extra, typed[i]);
+            i += 1;
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "kot"), 3);
+        assert_eq!(levenshtein("żółw", "żółw"), 0);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let strict = AnswerOptions {
+            ignore_case: false,
+            ignore_diacritics: false,
+            typo_tolerance: 0,
+        };
+        let lenient = AnswerOptions {
+            ignore_case: true,
+            ignore_diacritics: true,
+            typo_tolerance: 0,
+        };
+        assert_eq!(normalize("  Żółw   wodny ", &strict), "Żółw wodny");
+        assert_eq!(normalize("Żółw", &lenient), "zolw");
+        // Composed and decomposed forms compare equal
+        assert_eq!(normalize("z\u{307}", &strict), normalize("ż", &strict));
+    }
+
+    #[test]
+    fn test_suggested_quality() {
+        let options = AnswerOptions::default();
+        let grade = |typed: &str, expected: &str| check_answer(typed, expected, &options);
+
+        assert_eq!(grade("cat", "cat").suggested_quality, 5);
+        assert_eq!(grade("Cat", "cat").suggested_quality, 4);
+        assert_eq!(grade("elephnt", "elephant").suggested_quality, 3);
+        assert_eq!(grade("elefant", "elephant").suggested_quality, 2);
+        assert_eq!(grade("dog", "cat").suggested_quality, 1);
+        assert_eq!(grade("  ", "cat").suggested_quality, 0);
+
+        let check = grade("kitty", "cat; kitty");
+        assert_eq!(check.expected, "kitty");
+        assert_eq!(check.suggested_quality, 5);
+    }
+
+    #[test]
+    fn test_diff() {
+        let check = check_answer("helo wold", "hello world", &AnswerOptions::default());
+        let kinds: Vec<_> = check
+            .diff
+            .iter()
+            .map(|s| (s.kind, s.text.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (DiffKind::Same, "hel"),
+                (DiffKind::Missing, "l"),
+                (DiffKind::Same, "o wo"),
+                (DiffKind::Missing, "r"),
+                (DiffKind::Same, "ld"),
+            ]
+        );
+    }
+}
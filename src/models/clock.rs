@@ -0,0 +1,151 @@
+//! Source of the current date used for scheduling.
+//! The clock mode is stored in the database; the simulated date only moves
+//! when the day is advanced by hand, which is useful for testing schedules.
+//!
+//! Due-ness is evaluated on learning days rather than exact timestamps: a
+//! learning day starts at a configurable rollover hour in local time, and
+//! every card due before the next rollover is available for the whole day.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// Hour of local time at which a new learning day starts by default
+pub const DEFAULT_ROLLOVER_HOUR: u32 = 4;
+
+/// Where the current date comes from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClockMode {
+    /// Real system time
+    #[default]
+    System,
+    /// Stored date advanced with "Next Day"
+    Simulated,
+}
+
+impl ClockMode {
+    pub const ALL: [ClockMode; 2] = [ClockMode::System, ClockMode::Simulated];
+
+    /// Identifier stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClockMode::System => "system",
+            ClockMode::Simulated => "simulated",
+        }
+    }
+
+    /// Human readable name for the UI
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClockMode::System => "System time",
+            ClockMode::Simulated => "Simulated (Next Day)",
+        }
+    }
+}
+
+impl fmt::Display for ClockMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for ClockMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "system" => Ok(ClockMode::System),
+            "simulated" => Ok(ClockMode::Simulated),
+            other => Err(format!("Unknown clock mode '{}'", other)),
+        }
+    }
+}
+
+/// Calendar date of the learning day containing `time`
+fn learning_date<Tz: TimeZone>(time: &DateTime<Tz>, rollover_hour: u32) -> NaiveDate {
+    (time.naive_local() - Duration::hours(rollover_hour as i64)).date()
+}
+
+/// Rollover time on a date
+fn rollover_on<Tz: TimeZone>(
+    timezone: &Tz,
+    date: NaiveDate,
+    rollover_hour: u32,
+) -> Option<DateTime<Tz>> {
+    let start = date.and_hms_opt(rollover_hour, 0, 0)?;
+    // Around daylight saving changes the rollover time may be repeated or skipped
+    timezone.from_local_datetime(&start).earliest().or_else(|| {
+        timezone
+            .from_local_datetime(&(start + Duration::hours(1)))
+            .earliest()
+    })
+}
+
+/// Start of the learning day that contains `time`
+pub fn day_start<Tz: TimeZone>(time: &DateTime<Tz>, rollover_hour: u32) -> DateTime<Tz> {
+    rollover_on(
+        &time.timezone(),
+        learning_date(time, rollover_hour),
+        rollover_hour,
+    )
+    .unwrap_or_else(|| time.clone())
+}
+
+/// Start of the learning day following the one that contains `time`
+pub fn next_day_start<Tz: TimeZone>(time: &DateTime<Tz>, rollover_hour: u32) -> DateTime<Tz> {
+    learning_date(time, rollover_hour)
+        .succ_opt()
+        .and_then(|date| rollover_on(&time.timezone(), date, rollover_hour))
+        .unwrap_or_else(|| time.clone() + Duration::days(1))
+}
+
+/// Calendar date of the learning day containing `time`, in local time
+pub fn learning_day(time: SystemTime, rollover_hour: u32) -> NaiveDate {
+    let local: DateTime<Local> = time.into();
+    learning_date(&local, rollover_hour)
+}
+
+/// Cards due before this moment are due on the learning day containing `now`
+pub fn due_cutoff(now: SystemTime, rollover_hour: u32) -> SystemTime {
+    let local: DateTime<Local> = now.into();
+    next_day_start(&local, rollover_hour).into()
+}
+
+/// Start of the learning day containing `now`, in local time
+pub fn today_start(now: SystemTime, rollover_hour: u32) -> SystemTime {
+    let local: DateTime<Local> = now.into();
+    day_start(&local, rollover_hour).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn utc(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_day_starts_at_rollover_hour() {
+        assert_eq!(next_day_start(&utc(10, 23), 4), utc(11, 4));
+        assert_eq!(next_day_start(&utc(11, 2), 4), utc(11, 4));
+        assert_eq!(next_day_start(&utc(11, 4), 4), utc(12, 4));
+        assert_eq!(next_day_start(&utc(11, 2), 0), utc(12, 0));
+
+        assert_eq!(day_start(&utc(10, 23), 4), utc(10, 4));
+        assert_eq!(day_start(&utc(11, 2), 4), utc(10, 4));
+        assert_eq!(day_start(&utc(11, 4), 4), utc(11, 4));
+    }
+
+    #[test]
+    fn test_late_review_is_due_in_the_morning() {
+        // Reviewed at 23:00 with a 6 day interval
+        let next_review = utc(1, 23) + Duration::days(6);
+        let morning = utc(7, 8);
+
+        assert!(next_review > morning);
+        assert!(next_review < next_day_start(&morning, 4));
+    }
+}
@@ -1,13 +1,7 @@
 //! Container for all available decks
 use super::Deck;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct DeckSet {
     pub decks: Vec<Deck>,
 }
-
-impl Default for DeckSet {
-    fn default() -> Self {
-        Self { decks: Vec::new() }
-    }
-}
@@ -0,0 +1,190 @@
+//! Hierarchy of decks derived from their names.
+//! A deck named `Polish::Lesson 1::Verbs` is a sub-deck of `Polish::Lesson 1`,
+//! which is a sub-deck of `Polish`. Counts of a node include all of its sub-decks.
+
+use super::DeckSet;
+use std::collections::HashMap;
+
+/// Separator between the levels of a deck name
+pub const DECK_SEPARATOR: &str = "::";
+
+/// Name of the deck containing `name`, if it is a sub-deck
+pub fn parent_name(name: &str) -> Option<&str> {
+    name.rsplit_once(DECK_SEPARATOR).map(|(parent, _)| parent)
+}
+
+/// Last level of a deck name, e.g. "Verbs" for `Polish::Lesson 1::Verbs`
+pub fn leaf_name(name: &str) -> &str {
+    name.rsplit_once(DECK_SEPARATOR)
+        .map_or(name, |(_, leaf)| leaf)
+}
+
+/// Names of all decks containing `name`, outermost first
+pub fn ancestor_names(name: &str) -> Vec<&str> {
+    let mut ancestors = Vec::new();
+    let mut current = name;
+    while let Some(parent) = parent_name(current) {
+        ancestors.push(parent);
+        current = parent;
+    }
+    ancestors.reverse();
+    ancestors
+}
+
+/// True if `name` is `root` itself or one of its sub-decks at any depth
+pub fn is_in_subtree(name: &str, root: &str) -> bool {
+    name.strip_prefix(root)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(DECK_SEPARATOR))
+}
+
+/// Returns an error message unless every level of the name is non-empty
+pub fn validate_deck_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Deck name cannot be empty".to_string());
+    }
+    if name
+        .split(DECK_SEPARATOR)
+        .any(|level| level.trim().is_empty())
+    {
+        return Err(format!(
+            "Deck name '{}' has an empty level between '{}'",
+            name, DECK_SEPARATOR
+        ));
+    }
+    Ok(())
+}
+
+/// A deck with its sub-decks and counts rolled up from them
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeckNode {
+    /// Full name of the deck
+    pub name: String,
+    /// Index in `DeckSet::decks`, or None for a level without its own deck
+    pub deck_index: Option<usize>,
+    /// Cards of this deck and all sub-decks
+    pub card_count: usize,
+    /// Due cards of this deck and all sub-decks
+    pub due_count: usize,
+    pub children: Vec<DeckNode>,
+}
+
+impl DeckNode {
+    /// Last level of the name, shown in the tree
+    pub fn label(&self) -> &str {
+        leaf_name(&self.name)
+    }
+
+    /// Builds the deck tree, sorted by name
+    ///
+    /// `due_counts` maps deck names to their own due cards. Levels missing
+    /// from the set (e.g. decks created by an older version) become nodes
+    /// without a deck.
+    pub fn build(decks: &DeckSet, due_counts: &HashMap<String, usize>) -> Vec<DeckNode> {
+        let mut indices: Vec<usize> = (0..decks.decks.len()).collect();
+        indices.sort_by(|&a, &b| decks.decks[a].name.cmp(&decks.decks[b].name));
+
+        let mut roots: Vec<DeckNode> = Vec::new();
+        for index in indices {
+            let deck = &decks.decks[index];
+            let mut level = &mut roots;
+            for ancestor in ancestor_names(&deck.name) {
+                level = &mut child(level, ancestor).children;
+            }
+            let node = child(level, &deck.name);
+            node.deck_index = Some(index);
+            node.card_count = deck.flashcards.len();
+            node.due_count = due_counts.get(&deck.name).copied().unwrap_or(0);
+        }
+
+        for root in &mut roots {
+            root.roll_up();
+        }
+        roots
+    }
+
+    /// Adds the counts of all sub-decks to this node
+    fn roll_up(&mut self) {
+        for child in &mut self.children {
+            child.roll_up();
+            self.card_count += child.card_count;
+            self.due_count += child.due_count;
+        }
+    }
+}
+
+/// Finds the node named `name` among `nodes`, adding an empty one if missing
+fn child<'a>(nodes: &'a mut Vec<DeckNode>, name: &str) -> &'a mut DeckNode {
+    let position = match nodes.iter().position(|node| node.name == name) {
+        Some(position) => position,
+        None => {
+            nodes.push(DeckNode {
+                name: name.to_string(),
+                deck_index: None,
+                card_count: 0,
+                due_count: 0,
+                children: Vec::new(),
+            });
+            nodes.len() - 1
+        }
+    };
+    &mut nodes[position]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Deck, Flashcard};
+
+    fn deck(name: &str, cards: usize) -> Deck {
+        Deck {
+            name: name.to_string(),
+            flashcards: vec![Flashcard::default(); cards],
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(
+            parent_name("Polish::Lesson 1::Verbs"),
+            Some("Polish::Lesson 1")
+        );
+        assert_eq!(parent_name("Polish"), None);
+        assert_eq!(leaf_name("Polish::Lesson 1"), "Lesson 1");
+        assert_eq!(ancestor_names("a::b::c"), vec!["a", "a::b"]);
+        assert!(is_in_subtree("Polish::Lesson 1", "Polish"));
+        assert!(is_in_subtree("Polish", "Polish"));
+        assert!(!is_in_subtree("Polish 2", "Polish"));
+        assert!(validate_deck_name("Polish::Lesson 1").is_ok());
+        assert!(validate_deck_name("Polish:: ").is_err());
+    }
+
+    #[test]
+    fn test_tree_rolls_up_counts() {
+        let decks = DeckSet {
+            decks: vec![
+                deck("Polish::Lesson 2", 3),
+                deck("Polish", 1),
+                deck("German::Nouns", 4),
+                deck("Polish::Lesson 1", 2),
+            ],
+        };
+        let due = HashMap::from([
+            ("Polish::Lesson 1".to_string(), 2),
+            ("Polish".to_string(), 1),
+        ]);
+
+        let tree = DeckNode::build(&decks, &due);
+
+        assert_eq!(tree.len(), 2);
+        let german = &tree[0];
+        assert_eq!(german.deck_index, None);
+        assert_eq!(german.card_count, 4);
+        assert_eq!(german.children[0].label(), "Nouns");
+
+        let polish = &tree[1];
+        assert_eq!(polish.deck_index, Some(1));
+        assert_eq!((polish.card_count, polish.due_count), (6, 3));
+        let labels: Vec<&str> = polish.children.iter().map(DeckNode::label).collect();
+        assert_eq!(labels, vec!["Lesson 1", "Lesson 2"]);
+    }
+}
@@ -0,0 +1,107 @@
+//! Directions in which a flashcard is studied.
+//! Every direction has its own review data, so recognising a word and
+//! producing it are scheduled independently.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Which side of a flashcard is shown as the question
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CardDirection {
+    /// Term shown, definition recalled
+    #[default]
+    Forward,
+    /// Definition shown, term recalled
+    Reverse,
+}
+
+impl CardDirection {
+    /// Identifier stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CardDirection::Forward => "forward",
+            CardDirection::Reverse => "reverse",
+        }
+    }
+}
+
+impl fmt::Display for CardDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CardDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "forward" => Ok(CardDirection::Forward),
+            "reverse" => Ok(CardDirection::Reverse),
+            other => Err(format!("Unknown card direction '{}'", other)),
+        }
+    }
+}
+
+/// Directions studied for a deck or a single flashcard
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DirectionMode {
+    #[default]
+    Forward,
+    Reverse,
+    Both,
+}
+
+impl DirectionMode {
+    pub const ALL: [DirectionMode; 3] = [
+        DirectionMode::Forward,
+        DirectionMode::Reverse,
+        DirectionMode::Both,
+    ];
+
+    /// Identifier stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirectionMode::Forward => "forward",
+            DirectionMode::Reverse => "reverse",
+            DirectionMode::Both => "both",
+        }
+    }
+
+    /// Human readable name for the UI
+    pub fn label(&self) -> &'static str {
+        match self {
+            DirectionMode::Forward => "Term → Definition",
+            DirectionMode::Reverse => "Definition → Term",
+            DirectionMode::Both => "Both directions",
+        }
+    }
+
+    /// Card directions studied in this mode
+    pub fn directions(&self) -> &'static [CardDirection] {
+        match self {
+            DirectionMode::Forward => &[CardDirection::Forward],
+            DirectionMode::Reverse => &[CardDirection::Reverse],
+            DirectionMode::Both => &[CardDirection::Forward, CardDirection::Reverse],
+        }
+    }
+}
+
+impl fmt::Display for DirectionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for DirectionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "forward" => Ok(DirectionMode::Forward),
+            "reverse" => Ok(DirectionMode::Reverse),
+            "both" => Ok(DirectionMode::Both),
+            other => Err(format!("Unknown direction mode '{}'", other)),
+        }
+    }
+}
@@ -1,10 +1,41 @@
-//! Flashcard is a pair <term, definition>. Only text is used in terms and definitions
+//! Flashcard is a pair <term, definition>. Both sides are text and may have an
+//! image and an audio clip from the media store. Tags group cards across decks,
+//! e.g. by lesson or part of speech.
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Flashcard {
     pub term: String,
     pub definition: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Name of an image in the media store shown with the term
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term_image: Option<String>,
+    /// Name of an image in the media store shown with the definition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition_image: Option<String>,
+    /// Name of an audio clip in the media store played with the term
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term_audio: Option<String>,
+    /// Name of an audio clip in the media store played with the definition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition_audio: Option<String>,
+}
+
+impl Flashcard {
+    /// Names of all media files the card refers to
+    pub fn media_names(&self) -> impl Iterator<Item = &str> {
+        [
+            &self.term_image,
+            &self.definition_image,
+            &self.term_audio,
+            &self.definition_audio,
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+    }
 }
 
 #[cfg(test)]
@@ -16,6 +47,7 @@ mod tests {
         let card = Flashcard {
             term: "hello".to_string(),
             definition: "cześć".to_string(),
+            ..Flashcard::default()
         };
 
         assert_eq!(card.term, "hello");
@@ -27,6 +59,7 @@ mod tests {
         let card1 = Flashcard {
             term: "hello".to_string(),
             definition: "cześć".to_string(),
+            ..Flashcard::default()
         };
 
         let card2 = card1.clone();
@@ -0,0 +1,224 @@
+//! FSRS (Free Spaced Repetition Scheduler) implementation.
+//!
+//! FSRS models memory with two variables per card:
+//! - Stability (S): days until recall probability drops to 90%
+//! - Difficulty (D): 1-10, how hard it is to raise stability
+//! - Retrievability (R) is derived from S and the days elapsed since the last review
+//!
+//! Quality grades are mapped onto the four FSRS ratings:
+//! 0-2 = Again, 3 = Hard, 4 = Good, 5 = Easy.
+//! Uses the FSRS-4.5 default parameters.
+
+use super::ReviewData;
+use super::scheduler::Scheduler;
+use std::time::{Duration, SystemTime};
+
+const DECAY: f64 = -0.5;
+const FACTOR: f64 = 19.0 / 81.0;
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// FSRS-4.5 default model weights
+pub const DEFAULT_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+/// FSRS scheduler with its model parameters
+#[derive(Clone, Debug)]
+pub struct FsrsScheduler {
+    pub weights: [f64; 17],
+    /// Target probability of recall when a card comes due (0.0-1.0)
+    pub desired_retention: f64,
+    pub maximum_interval: i32,
+}
+
+impl Default for FsrsScheduler {
+    fn default() -> Self {
+        Self {
+            weights: DEFAULT_WEIGHTS,
+            desired_retention: 0.9,
+            maximum_interval: 36500,
+        }
+    }
+}
+
+/// Maps a 0-5 quality grade to an FSRS rating (1 = Again .. 4 = Easy)
+fn rating(quality: u8) -> u8 {
+    match quality {
+        0..=2 => 1,
+        3 => 2,
+        4 => 3,
+        _ => 4,
+    }
+}
+
+impl FsrsScheduler {
+    fn initial_stability(&self, rating: u8) -> f64 {
+        self.weights[(rating - 1) as usize].max(0.1)
+    }
+
+    fn initial_difficulty(&self, rating: u8) -> f64 {
+        (self.weights[4] - (rating as f64 - 3.0) * self.weights[5]).clamp(1.0, 10.0)
+    }
+
+    /// Probability of recall after `elapsed_days` for a card with stability `stability`
+    pub fn retrievability(elapsed_days: f64, stability: f64) -> f64 {
+        (1.0 + FACTOR * elapsed_days / stability).powf(DECAY)
+    }
+
+    fn next_interval(&self, stability: f64) -> i32 {
+        let interval = stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0);
+        (interval.round() as i32).clamp(1, self.maximum_interval)
+    }
+
+    fn next_difficulty(&self, difficulty: f64, rating: u8) -> f64 {
+        let next = difficulty - self.weights[6] * (rating as f64 - 3.0);
+        // Mean reversion towards the initial difficulty of a "Good" answer
+        (self.weights[7] * self.initial_difficulty(3) + (1.0 - self.weights[7]) * next)
+            .clamp(1.0, 10.0)
+    }
+
+    fn recall_stability(&self, difficulty: f64, stability: f64, r: f64, rating: u8) -> f64 {
+        let w = &self.weights;
+        let hard_penalty = if rating == 2 { w[15] } else { 1.0 };
+        let easy_bonus = if rating == 4 { w[16] } else { 1.0 };
+        stability
+            * (1.0
+                + w[8].exp()
+                    * (11.0 - difficulty)
+                    * stability.powf(-w[9])
+                    * ((w[10] * (1.0 - r)).exp() - 1.0)
+                    * hard_penalty
+                    * easy_bonus)
+    }
+
+    fn forget_stability(&self, difficulty: f64, stability: f64, r: f64) -> f64 {
+        let w = &self.weights;
+        let next = w[11]
+            * difficulty.powf(-w[12])
+            * ((stability + 1.0).powf(w[13]) - 1.0)
+            * (w[14] * (1.0 - r)).exp();
+        next.min(stability).max(0.1)
+    }
+
+    /// Days since the card was last reviewed, derived from its current due date and interval
+    fn elapsed_days(review_data: &ReviewData, current_date: SystemTime) -> f64 {
+        let last_review = review_data.next_review_date
+            - Duration::from_secs(review_data.interval_days.max(0) as u64 * 24 * 60 * 60);
+        current_date
+            .duration_since(last_review)
+            .map(|d| d.as_secs_f64() / SECONDS_PER_DAY)
+            .unwrap_or(0.0)
+    }
+}
+
+impl Scheduler for FsrsScheduler {
+    fn next_review(
+        &self,
+        review_data: &ReviewData,
+        quality: u8,
+        current_date: SystemTime,
+    ) -> ReviewData {
+        let rating = rating(quality);
+
+        // Cards without FSRS state (new, or previously scheduled by SM-2) start fresh
+        let (stability, difficulty) = if review_data.stability <= 0.0 {
+            (
+                self.initial_stability(rating),
+                self.initial_difficulty(rating),
+            )
+        } else {
+            let elapsed = Self::elapsed_days(review_data, current_date);
+            let r = Self::retrievability(elapsed, review_data.stability);
+            let stability = if rating == 1 {
+                self.forget_stability(review_data.difficulty, review_data.stability, r)
+            } else {
+                self.recall_stability(review_data.difficulty, review_data.stability, r, rating)
+            };
+            (
+                stability,
+                self.next_difficulty(review_data.difficulty, rating),
+            )
+        };
+
+        let (new_interval, new_repetitions) = if rating == 1 {
+            // Forgotten cards are relearned in the current session
+            (0, 0)
+        } else {
+            (self.next_interval(stability), review_data.repetitions + 1)
+        };
+
+        let next_date = current_date + Duration::from_secs((new_interval as u64) * 24 * 60 * 60);
+
+        ReviewData {
+            flashcard_id: review_data.flashcard_id,
+            direction: review_data.direction,
+            easiness_factor: review_data.easiness_factor,
+            interval_days: new_interval,
+            repetitions: new_repetitions,
+            next_review_date: next_date,
+            stability,
+            difficulty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_support::review_data;
+
+    fn new_card() -> ReviewData {
+        review_data(1, SystemTime::now())
+    }
+
+    #[test]
+    fn test_first_review_uses_initial_stability() {
+        let fsrs = FsrsScheduler::default();
+        let next = fsrs.next_review(&new_card(), 4, SystemTime::now());
+        assert!((next.stability - DEFAULT_WEIGHTS[2]).abs() < 1e-9);
+        assert_eq!(next.interval_days, 4);
+        assert_eq!(next.repetitions, 1);
+    }
+
+    #[test]
+    fn test_again_resets_interval() {
+        let fsrs = FsrsScheduler::default();
+        let next = fsrs.next_review(&new_card(), 1, SystemTime::now());
+        assert_eq!(next.interval_days, 0);
+        assert_eq!(next.repetitions, 0);
+    }
+
+    #[test]
+    fn test_easy_cards_are_less_difficult() {
+        let fsrs = FsrsScheduler::default();
+        let again = fsrs.next_review(&new_card(), 0, SystemTime::now());
+        let easy = fsrs.next_review(&new_card(), 5, SystemTime::now());
+        assert!(easy.difficulty < again.difficulty);
+        assert!(easy.interval_days > again.interval_days);
+    }
+
+    #[test]
+    fn test_successful_reviews_grow_interval() {
+        let fsrs = FsrsScheduler::default();
+        let mut now = SystemTime::now();
+        let mut card = fsrs.next_review(&new_card(), 4, now);
+        let mut last_interval = card.interval_days;
+
+        for _ in 0..4 {
+            now = card.next_review_date;
+            card = fsrs.next_review(&card, 4, now);
+            assert!(card.interval_days > last_interval);
+            last_interval = card.interval_days;
+        }
+    }
+
+    #[test]
+    fn test_lapse_lowers_stability() {
+        let fsrs = FsrsScheduler::default();
+        let first = fsrs.next_review(&new_card(), 4, SystemTime::now());
+        let lapsed = fsrs.next_review(&first, 0, first.next_review_date);
+        assert!(lapsed.stability < first.stability);
+        assert!(lapsed.difficulty > first.difficulty);
+    }
+}
@@ -1,18 +1,21 @@
 //! Wrapper for flashcards that tracks learning progress.
-use super::Flashcard;
+use super::{CardDirection, Flashcard};
 use std::time::SystemTime;
 
 #[derive(Clone)]
 pub struct LearningCard {
     pub flashcard: Flashcard,
+    /// Direction being studied, deciding which side is the question
+    pub direction: CardDirection,
     pub is_learned: bool,
     pub last_learned_at: Option<SystemTime>,
 }
 
 impl LearningCard {
-    pub fn new(flashcard: Flashcard) -> Self {
+    pub fn new(flashcard: Flashcard, direction: CardDirection) -> Self {
         Self {
             flashcard,
+            direction,
             is_learned: false,
             last_learned_at: None,
         }
@@ -22,4 +25,68 @@ impl LearningCard {
         self.is_learned = true;
         self.last_learned_at = Some(SystemTime::now());
     }
+
+    /// Side shown before revealing the answer
+    pub fn prompt(&self) -> &str {
+        match self.direction {
+            CardDirection::Forward => &self.flashcard.term,
+            CardDirection::Reverse => &self.flashcard.definition,
+        }
+    }
+
+    /// Side the learner has to recall
+    pub fn answer(&self) -> &str {
+        match self.direction {
+            CardDirection::Forward => &self.flashcard.definition,
+            CardDirection::Reverse => &self.flashcard.term,
+        }
+    }
+
+    /// Image shown with the prompt, named by the media store
+    pub fn prompt_image(&self) -> Option<&str> {
+        match self.direction {
+            CardDirection::Forward => self.flashcard.term_image.as_deref(),
+            CardDirection::Reverse => self.flashcard.definition_image.as_deref(),
+        }
+    }
+
+    /// Image shown with the answer, named by the media store
+    pub fn answer_image(&self) -> Option<&str> {
+        match self.direction {
+            CardDirection::Forward => self.flashcard.definition_image.as_deref(),
+            CardDirection::Reverse => self.flashcard.term_image.as_deref(),
+        }
+    }
+
+    /// Audio clip played with the prompt, named by the media store
+    pub fn prompt_audio(&self) -> Option<&str> {
+        match self.direction {
+            CardDirection::Forward => self.flashcard.term_audio.as_deref(),
+            CardDirection::Reverse => self.flashcard.definition_audio.as_deref(),
+        }
+    }
+
+    /// Audio clip played with the answer, named by the media store
+    pub fn answer_audio(&self) -> Option<&str> {
+        match self.direction {
+            CardDirection::Forward => self.flashcard.definition_audio.as_deref(),
+            CardDirection::Reverse => self.flashcard.term_audio.as_deref(),
+        }
+    }
+
+    /// Name of the prompt side, e.g. "Term"
+    pub fn prompt_label(&self) -> &'static str {
+        match self.direction {
+            CardDirection::Forward => "Term",
+            CardDirection::Reverse => "Definition",
+        }
+    }
+
+    /// Name of the answer side, e.g. "Definition"
+    pub fn answer_label(&self) -> &'static str {
+        match self.direction {
+            CardDirection::Forward => "Definition",
+            CardDirection::Reverse => "Term",
+        }
+    }
 }
@@ -1,9 +1,19 @@
 //! Learning session management for spaced repetition practice.
-//! Handles multi-round flashcard review with SM-2 algorithm integration.
+//! Handles multi-round flashcard review with pluggable scheduler integration.
+//! A session may combine cards of several decks, e.g. all cards with a tag.
 
-use super::{LearningCard, ReviewData};
+use super::answer::check_answer;
+use super::markdown::to_plain_text;
+use super::quiz::{DEFAULT_CHOICE_COUNT, build_choices};
+use super::{
+    AnswerCheck, AnswerOptions, CardDirection, Flashcard, LearningCard, MultipleChoice, ReviewData,
+    ReviewLogEntry, Scheduler, SchedulerKind, StudyMode,
+};
+use crate::error::Result;
 use rusqlite::Connection;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
 
 /// Manages a learning session with multiple review rounds.
 /// Cards that aren't mastered (grade < 3) are repeated in subsequent rounds.
@@ -15,19 +25,38 @@ pub struct LearningSession {
     pub show_definition: bool,
     pub conn: Arc<Mutex<Connection>>,
     pub round_number: usize,
+    pub scheduler: Box<dyn Scheduler>,
+    /// Schedulers of cards from decks other than the one `scheduler` belongs to
+    card_schedulers: HashMap<i64, Box<dyn Scheduler>>,
+    /// When the current card was shown, used to record time taken per review
+    pub card_shown_at: Instant,
+    pub mode: StudyMode,
+    /// Answer typed for the current card in `StudyMode::Typed`
+    pub typed_answer: String,
+    /// Result of checking `typed_answer`, set once the answer is revealed
+    pub answer_check: Option<AnswerCheck>,
+    /// Cards whose answers serve as distractors in `StudyMode::MultipleChoice`
+    pub choice_pool: Vec<Flashcard>,
+    /// Seed for shuffling multiple-choice questions
+    pub seed: u64,
+    multiple_choice: Option<MultipleChoice>,
+    /// Choice picked for the current card
+    pub selected_choice: Option<usize>,
 }
 
 impl LearningSession {
     /// Creates a new learning session from cards that are due for review.
+    /// Grades are turned into new review data by the deck's scheduler.
     pub fn new_from_due_cards(
         deck_name: String,
         cards: Vec<(i64, crate::models::Flashcard, ReviewData)>,
+        scheduler: Box<dyn Scheduler>,
         conn: Arc<Mutex<Connection>>,
     ) -> Self {
         // Wrap flashcards in LearningCard for progress tracking
         let learning_cards: Vec<_> = cards
             .into_iter()
-            .map(|(id, fc, rd)| (id, LearningCard::new(fc), rd))
+            .map(|(id, fc, rd)| (id, LearningCard::new(fc, rd.direction), rd))
             .collect();
 
         let indices: Vec<usize> = (0..learning_cards.len()).collect();
@@ -40,6 +69,41 @@ impl LearningSession {
             show_definition: false,
             conn,
             round_number: 1,
+            scheduler,
+            card_schedulers: HashMap::new(),
+            card_shown_at: Instant::now(),
+            mode: StudyMode::default(),
+            typed_answer: String::new(),
+            answer_check: None,
+            choice_pool: Vec::new(),
+            seed: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default(),
+            multiple_choice: None,
+            selected_choice: None,
+        }
+    }
+
+    /// Creates a session from cards of several decks, e.g. a deck tree or those
+    /// matching a tag expression. Every card is graded by the scheduler of its own deck.
+    pub fn new_from_several_decks(
+        name: String,
+        cards: Vec<(i64, Flashcard, ReviewData, SchedulerKind)>,
+        conn: Arc<Mutex<Connection>>,
+    ) -> Self {
+        let card_schedulers = cards
+            .iter()
+            .map(|(id, _, _, kind)| (*id, kind.scheduler()))
+            .collect();
+        let cards = cards
+            .into_iter()
+            .map(|(id, flashcard, review, _)| (id, flashcard, review))
+            .collect();
+
+        Self {
+            card_schedulers,
+            ..Self::new_from_due_cards(name, cards, SchedulerKind::default().scheduler(), conn)
         }
     }
 
@@ -53,10 +117,68 @@ impl LearningSession {
         self.show_definition = !self.show_definition;
     }
 
+    /// Checks the typed answer against the current card and reveals the answer
+    pub fn check_typed_answer(&mut self, options: &AnswerOptions) -> Option<&AnswerCheck> {
+        let card = self.current_card()?;
+        let expected = to_plain_text(card.answer());
+        self.answer_check = Some(check_answer(&self.typed_answer, &expected, options));
+        self.show_definition = true;
+        self.answer_check.as_ref()
+    }
+
+    /// Multiple-choice question for the current card, built on first use
+    ///
+    /// Distractors are the answers of other cards in `choice_pool`, taken from
+    /// the same side as the current card's answer. Choices are plain text, as
+    /// they are shown on buttons.
+    pub fn current_choices(&mut self) -> Option<&MultipleChoice> {
+        if self.multiple_choice.is_none() {
+            let &idx = self.current_round_cards.get(self.current_index)?;
+            let (id, card, _) = self.all_cards.get(idx)?;
+            let candidates: Vec<String> = self
+                .choice_pool
+                .iter()
+                .map(|other| match card.direction {
+                    CardDirection::Forward => to_plain_text(&other.definition),
+                    CardDirection::Reverse => to_plain_text(&other.term),
+                })
+                .collect();
+            let candidates: Vec<&str> = candidates.iter().map(String::as_str).collect();
+            let seed = self.seed ^ (*id as u64).rotate_left(32) ^ self.round_number as u64;
+            self.multiple_choice = Some(build_choices(
+                &to_plain_text(card.answer()),
+                &candidates,
+                DEFAULT_CHOICE_COUNT,
+                seed,
+            ));
+        }
+        self.multiple_choice.as_ref()
+    }
+
+    /// Picks a choice of the current question and reveals the answer
+    ///
+    /// Returns the quality grade for the pick, to be passed to `grade_current_card`.
+    pub fn choose(&mut self, index: usize) -> Option<u8> {
+        let quality = self.current_choices()?.quality_for(index);
+        self.selected_choice = Some(index);
+        self.show_definition = true;
+        Some(quality)
+    }
+
+    /// Clears the answer state before showing a card
+    fn reset_answer(&mut self) {
+        self.show_definition = false;
+        self.typed_answer.clear();
+        self.answer_check = None;
+        self.multiple_choice = None;
+        self.selected_choice = None;
+        self.card_shown_at = Instant::now();
+    }
+
     pub fn next_card(&mut self) {
         if self.current_index < self.current_round_cards.len() - 1 {
             self.current_index += 1;
-            self.show_definition = false;
+            self.reset_answer();
         } else {
             // End of round - check if there are cards to review
             self.start_next_round();
@@ -83,8 +205,8 @@ impl LearningSession {
             // There are cards to review - start new round
             self.current_round_cards = failed_indices;
             self.current_index = 0;
-            self.show_definition = false;
             self.round_number += 1;
+            self.reset_answer();
 
             // Reset is_learned for these cards (they'll be shown again)
             for &idx in &self.current_round_cards {
@@ -96,32 +218,50 @@ impl LearningSession {
         // If failed_indices is empty, session ends (is_completed() = true)
     }
 
-    /// Grades the current card and updates its review data using SM-2 algorithm.
+    /// Grades the current card and updates its review data using the session's scheduler.
+    /// Every grade is also appended to the review log.
     /// Cards with grade >= 3 are marked as learned for this session.
-    pub fn grade_current_card(&mut self, quality: u8) {
-        if let Some(&actual_idx) = self.current_round_cards.get(self.current_index) {
-            if let Some((_, card, review_data)) = self.all_cards.get_mut(actual_idx) {
-                // Mark as learned only if grade >= 3
-                if quality >= 3 {
-                    card.mark_as_learned();
-                } else {
-                    card.is_learned = false; // Will be repeated in next round
-                }
+    ///
+    /// The card is only marked once its progress has been saved, so a failed
+    /// write leaves the session unchanged and the grade can be retried.
+    pub fn grade_current_card(&mut self, quality: u8) -> Result<()> {
+        if let Some(&actual_idx) = self.current_round_cards.get(self.current_index)
+            && let Some((id, card, review_data)) = self.all_cards.get_mut(actual_idx)
+        {
+            // Calculate next review using the deck's scheduler
+            let conn = self.conn.lock()?;
+            let current_date = crate::database::db::get_current_date(&conn)?;
+
+            let scheduler = self.card_schedulers.get(id).unwrap_or(&self.scheduler);
+            let new_review = scheduler.next_review(review_data, quality, current_date);
 
-                // Calculate next review using SM-2
-                let conn = self.conn.lock().unwrap();
-                let current_date = crate::database::db::get_current_date(&conn).unwrap();
+            let entry = ReviewLogEntry {
+                id: 0,
+                flashcard_id: new_review.flashcard_id,
+                direction: new_review.direction,
+                reviewed_at: current_date,
+                quality: quality.min(5),
+                previous_interval_days: review_data.interval_days,
+                new_interval_days: new_review.interval_days,
+                previous_easiness_factor: review_data.easiness_factor,
+                new_easiness_factor: new_review.easiness_factor,
+                time_taken: self.card_shown_at.elapsed(),
+            };
 
-                let new_review =
-                    crate::models::sm2::calculate_next_review(review_data, quality, current_date);
+            // Update in database
+            crate::database::db::record_review(&new_review, &entry, &conn)?;
 
-                // Update in database
-                let _ = crate::database::db::update_review_data(&new_review, &conn);
+            // Update in memory
+            *review_data = new_review;
 
-                // Update in memory
-                *review_data = new_review;
+            // Mark as learned only if grade >= 3
+            if quality >= 3 {
+                card.mark_as_learned();
+            } else {
+                card.is_learned = false; // Will be repeated in next round
             }
         }
+        Ok(())
     }
 
     pub fn learned_count(&self) -> usize {
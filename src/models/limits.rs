@@ -0,0 +1,126 @@
+//! Daily limits on the number of cards studied per deck.
+//! New cards (never reviewed) and reviews are limited separately, so importing
+//! a large deck introduces its cards a few at a time. Cards studied earlier on
+//! the same learning day count towards the limits.
+
+use super::{Flashcard, ReviewData};
+
+pub const DEFAULT_NEW_CARDS_PER_DAY: u32 = 20;
+pub const DEFAULT_REVIEWS_PER_DAY: u32 = 200;
+
+/// Maximum number of cards studied per learning day
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DailyLimits {
+    pub new_cards: u32,
+    pub reviews: u32,
+}
+
+impl Default for DailyLimits {
+    fn default() -> Self {
+        Self {
+            new_cards: DEFAULT_NEW_CARDS_PER_DAY,
+            reviews: DEFAULT_REVIEWS_PER_DAY,
+        }
+    }
+}
+
+/// Cards of a deck already studied on the current learning day
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StudiedToday {
+    /// Cards reviewed for the first time
+    pub new_cards: u32,
+    pub reviews: u32,
+}
+
+impl DailyLimits {
+    /// New cards and reviews that may still be studied today
+    pub fn remaining(&self, studied: &StudiedToday) -> (usize, usize) {
+        (
+            self.new_cards.saturating_sub(studied.new_cards) as usize,
+            self.reviews.saturating_sub(studied.reviews) as usize,
+        )
+    }
+}
+
+/// Picks the due cards to study within the remaining limits
+///
+/// Reviews keep their due order and come first; new cards follow in the
+/// order they were added in.
+pub fn apply_limits(
+    due: Vec<(i64, Flashcard, ReviewData)>,
+    is_new: impl Fn(&ReviewData) -> bool,
+    limits: &DailyLimits,
+    studied: &StudiedToday,
+) -> Vec<(i64, Flashcard, ReviewData)> {
+    let (new_left, reviews_left) = limits.remaining(studied);
+    let (mut new_cards, reviews): (Vec<_>, Vec<_>) =
+        due.into_iter().partition(|(_, _, review)| is_new(review));
+    new_cards.sort_by_key(|(id, _, review)| (*id, review.direction.as_str()));
+
+    reviews
+        .into_iter()
+        .take(reviews_left)
+        .chain(new_cards.into_iter().take(new_left))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_support::review_data;
+    use std::time::SystemTime;
+
+    fn due(ids: impl Iterator<Item = i64>, repetitions: i32) -> Vec<(i64, Flashcard, ReviewData)> {
+        ids.map(|id| {
+            (
+                id,
+                Flashcard {
+                    term: id.to_string(),
+                    definition: id.to_string(),
+                    tags: Vec::new(),
+                    ..Flashcard::default()
+                },
+                ReviewData {
+                    interval_days: repetitions,
+                    repetitions,
+                    ..review_data(id, SystemTime::UNIX_EPOCH)
+                },
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_limits_new_cards_and_reviews() {
+        let mut cards = due((100..105).rev(), 0);
+        cards.extend(due(1..4, 2));
+        let limits = DailyLimits {
+            new_cards: 3,
+            reviews: 2,
+        };
+
+        let picked = apply_limits(
+            cards,
+            |review| review.repetitions == 0,
+            &limits,
+            &StudiedToday {
+                new_cards: 1,
+                reviews: 0,
+            },
+        );
+
+        let ids: Vec<i64> = picked.iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(ids, vec![1, 2, 100, 101]);
+    }
+
+    #[test]
+    fn test_exhausted_limits() {
+        let limits = DailyLimits::default();
+        let studied = StudiedToday {
+            new_cards: 25,
+            reviews: 200,
+        };
+        assert_eq!(limits.remaining(&studied), (0, 0));
+        assert!(apply_limits(due(1..3, 0), |_| true, &limits, &studied).is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+//! Markdown subset used for formatting card faces.
+//! Supported are `**bold**`, `*italic*` or `_italic_`, `` `code` ``, bulleted
+//! (`- `, `* `) and numbered (`1. `) lists and code blocks fenced with ```.
+//! Every line break is kept, and `\` makes the next character literal.
+//! Anything else is shown as typed.
+
+/// Run of text with the same formatting
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+}
+
+/// Line or block of a card face
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Block {
+    /// Line of text; an empty one separates paragraphs
+    Line(Vec<Span>),
+    Bullet(Vec<Span>),
+    Numbered(u32, Vec<Span>),
+    /// Lines of a fenced code block, shown as typed
+    Code(String),
+}
+
+const FENCE: &str = "```";
+
+/// Splits card text into blocks
+pub fn parse(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = text.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with(FENCE) {
+            // An unclosed fence runs to the end of the text
+            let code: Vec<&str> = lines
+                .by_ref()
+                .take_while(|line| !line.trim_start().starts_with(FENCE))
+                .collect();
+            blocks.push(Block::Code(code.join("\n")));
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            blocks.push(Block::Bullet(parse_inline(item)));
+        } else if let Some((number, item)) = numbered_item(trimmed) {
+            blocks.push(Block::Numbered(number, parse_inline(item)));
+        } else {
+            blocks.push(Block::Line(parse_inline(line)));
+        }
+    }
+    blocks
+}
+
+/// Number and text of a line like `2. text`
+fn numbered_item(line: &str) -> Option<(u32, &str)> {
+    let (number, item) = line.split_once(". ")?;
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((number.parse().ok()?, item))
+}
+
+/// Parses the inline formatting of a single line
+pub fn parse_inline(line: &str) -> Vec<Span> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut style = Span::default();
+    let mut i = 0;
+
+    let push = |spans: &mut Vec<Span>, style: &Span, text: &str| match spans.last_mut() {
+        Some(last)
+            if (last.bold, last.italic, last.code) == (style.bold, style.italic, style.code) =>
+        {
+            last.text.push_str(text)
+        }
+        _ => spans.push(Span {
+            text: text.to_string(),
+            ..style.clone()
+        }),
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i + 1..];
+
+        if c == '\\' && !rest.is_empty() {
+            push(&mut spans, &style, &rest[0].to_string());
+            i += 2;
+        } else if c == '`' {
+            match rest.iter().position(|&r| r == '`') {
+                Some(end) => {
+                    let code = Span {
+                        code: true,
+                        ..style.clone()
+                    };
+                    push(&mut spans, &code, &rest[..end].iter().collect::<String>());
+                    i += end + 2;
+                }
+                None => {
+                    push(&mut spans, &style, "`");
+                    i += 1;
+                }
+            }
+        } else if c == '*' && rest.first() == Some(&'*') {
+            if style.bold || contains(&rest[1..], &['*', '*']) {
+                style.bold = !style.bold;
+            } else {
+                push(&mut spans, &style, "**");
+            }
+            i += 2;
+        } else if (c == '*' || c == '_') && is_italic_delimiter(&chars, i, style.italic) {
+            style.italic = !style.italic;
+            i += 1;
+        } else {
+            push(&mut spans, &style, &c.to_string());
+            i += 1;
+        }
+    }
+    spans
+}
+
+/// True if `needle` occurs in `haystack`
+fn contains(haystack: &[char], needle: &[char]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// Decides whether `*` or `_` at `i` opens italics, or closes them if `closing`
+///
+/// An opening delimiter must be followed by text and needs a closing one later
+/// on the line, which must follow text. `_` inside a word, as in `snake_case`,
+/// is kept as typed.
+fn is_italic_delimiter(chars: &[char], i: usize, closing: bool) -> bool {
+    let c = chars[i];
+    let before = i.checked_sub(1).map(|p| chars[p]);
+    let after = chars.get(i + 1).copied();
+    let inside_word = c == '_'
+        && before.is_some_and(char::is_alphanumeric)
+        && after.is_some_and(char::is_alphanumeric);
+    if inside_word {
+        return false;
+    }
+
+    if closing {
+        before.is_some_and(|b| !b.is_whitespace())
+    } else {
+        after.is_some_and(|a| !a.is_whitespace())
+            && chars[i + 1..]
+                .iter()
+                .enumerate()
+                .any(|(j, &r)| r == c && is_italic_delimiter(chars, i + 1 + j, true))
+    }
+}
+
+/// Text without formatting, e.g. to compare typed answers with
+pub fn to_plain_text(text: &str) -> String {
+    let spans_text = |spans: &[Span]| spans.iter().map(|s| s.text.as_str()).collect::<String>();
+    parse(text)
+        .iter()
+        .map(|block| match block {
+            Block::Line(spans) => spans_text(spans),
+            Block::Bullet(spans) => format!("- {}", spans_text(spans)),
+            Block::Numbered(number, spans) => format!("{}. {}", number, spans_text(spans)),
+            Block::Code(code) => code.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, bold: bool, italic: bool, code: bool) -> Span {
+        Span {
+            text: text.to_string(),
+            bold,
+            italic,
+            code,
+        }
+    }
+
+    #[test]
+    fn test_inline_formatting() {
+        assert_eq!(
+            parse_inline("a **bold** and *it* `x*y`"),
+            vec![
+                span("a ", false, false, false),
+                span("bold", true, false, false),
+                span(" and ", false, false, false),
+                span("it", false, true, false),
+                span(" ", false, false, false),
+                span("x*y", false, false, true),
+            ]
+        );
+        // Unmatched and escaped delimiters stay as typed
+        assert_eq!(
+            to_plain_text("2 * 3, my_var_name, \\*x\\*, **open"),
+            "2 * 3, my_var_name, *x*, **open"
+        );
+        assert_eq!(to_plain_text("_to be_"), "to be");
+    }
+
+    #[test]
+    fn test_blocks() {
+        let blocks = parse("Verbs:\n- **biec**\n2. pływać\n```\nfn main() {}\n```\n");
+
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(
+            blocks[1],
+            Block::Bullet(vec![span("biec", true, false, false)])
+        );
+        assert!(matches!(&blocks[2], Block::Numbered(2, _)));
+        assert_eq!(blocks[3], Block::Code("fn main() {}".to_string()));
+        assert_eq!(to_plain_text("Verbs:\n- **biec**"), "Verbs:\n- biec");
+    }
+}
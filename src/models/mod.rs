@@ -1,14 +1,40 @@
+pub mod answer;
+pub mod clock;
 pub mod deck;
 pub mod deck_set;
+pub mod deck_tree;
+pub mod direction;
 pub mod flashcard;
+pub mod fsrs;
 pub mod learning_card;
 pub mod learning_session;
+pub mod limits;
+pub mod markdown;
+pub mod quiz;
 pub mod review_data;
+pub mod review_log;
+mod rng;
+pub mod scheduler;
+pub mod simulator;
 pub mod sm2;
+pub mod statistics;
+pub mod tag_filter;
+#[cfg(test)]
+pub(crate) mod test_support;
 
+pub use answer::{AnswerCheck, AnswerOptions, StudyMode};
+pub use clock::{ClockMode, DEFAULT_ROLLOVER_HOUR};
 pub use deck::Deck;
 pub use deck_set::DeckSet;
+pub use deck_tree::DeckNode;
+pub use direction::{CardDirection, DirectionMode};
 pub use flashcard::Flashcard;
 pub use learning_card::LearningCard;
 pub use learning_session::LearningSession;
+pub use limits::{DailyLimits, StudiedToday};
+pub use quiz::MultipleChoice;
 pub use review_data::ReviewData;
+pub use review_log::ReviewLogEntry;
+pub use scheduler::{Scheduler, SchedulerKind};
+pub use statistics::Statistics;
+pub use tag_filter::TagFilter;
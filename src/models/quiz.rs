@@ -0,0 +1,102 @@
+//! Multiple-choice questions built from the cards of a deck.
+//! The correct answer is shown among distractors taken from the answers of
+//! other cards. Choices are shuffled with a seeded generator so a question can
+//! be reproduced, and the result is mapped to an SM-2 quality grade.
+
+use super::rng::Rng;
+use std::collections::HashSet;
+
+/// Number of choices shown, including the correct one
+pub const DEFAULT_CHOICE_COUNT: usize = 4;
+
+/// Grade for picking the correct answer; recognising is easier than recalling,
+/// so it never counts as a perfect response
+pub const CORRECT_CHOICE_QUALITY: u8 = 4;
+
+/// Grade for picking a wrong answer
+pub const WRONG_CHOICE_QUALITY: u8 = 1;
+
+/// A question with its shuffled choices
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultipleChoice {
+    pub choices: Vec<String>,
+    /// Index of the correct answer in `choices`
+    pub correct_index: usize,
+}
+
+impl MultipleChoice {
+    /// Quality grade for picking the choice at `index`
+    pub fn quality_for(&self, index: usize) -> u8 {
+        if index == self.correct_index {
+            CORRECT_CHOICE_QUALITY
+        } else {
+            WRONG_CHOICE_QUALITY
+        }
+    }
+}
+
+/// Builds a question for `answer` with up to `count - 1` distractors
+///
+/// Candidates equal to the answer (ignoring case) and duplicates are skipped,
+/// so a small deck may yield fewer choices. The same seed always gives the
+/// same question.
+pub fn build_choices(answer: &str, candidates: &[&str], count: usize, seed: u64) -> MultipleChoice {
+    let mut rng = Rng(seed);
+
+    let mut seen = HashSet::from([answer.trim().to_lowercase()]);
+    let mut distractors: Vec<&str> = candidates
+        .iter()
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty() && seen.insert(c.to_lowercase()))
+        .collect();
+    rng.shuffle(&mut distractors);
+    distractors.truncate(count.saturating_sub(1));
+
+    let mut choices: Vec<String> = distractors.into_iter().map(str::to_string).collect();
+    choices.push(answer.to_string());
+    rng.shuffle(&mut choices);
+    let correct_index = choices.iter().position(|c| c == answer).unwrap_or_default();
+
+    MultipleChoice {
+        choices,
+        correct_index,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CANDIDATES: [&str; 6] = ["dog", "fish", "Cat", "bird", "dog", "horse"];
+
+    #[test]
+    fn test_choices_contain_answer_once() {
+        let question = build_choices("cat", &CANDIDATES, 4, 7);
+
+        assert_eq!(question.choices.len(), 4);
+        assert_eq!(question.choices[question.correct_index], "cat");
+        let distinct: HashSet<_> = question.choices.iter().map(|c| c.to_lowercase()).collect();
+        assert_eq!(distinct.len(), 4);
+        assert_eq!(question.quality_for(question.correct_index), 4);
+        assert_eq!(question.quality_for((question.correct_index + 1) % 4), 1);
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_question() {
+        assert_eq!(
+            build_choices("cat", &CANDIDATES, 4, 42),
+            build_choices("cat", &CANDIDATES, 4, 42)
+        );
+        let positions: HashSet<_> = (0..20)
+            .map(|seed| build_choices("cat", &CANDIDATES, 4, seed).correct_index)
+            .collect();
+        assert!(positions.len() > 1);
+    }
+
+    #[test]
+    fn test_small_deck() {
+        let question = build_choices("cat", &["cat"], 4, 1);
+        assert_eq!(question.choices, vec!["cat"]);
+        assert_eq!(question.correct_index, 0);
+    }
+}
@@ -1,10 +1,17 @@
+use super::CardDirection;
 use std::time::SystemTime;
 
 #[derive(Clone, Debug)]
 pub struct ReviewData {
     pub flashcard_id: i64,
+    /// Direction these review data schedule; each direction is scheduled separately
+    pub direction: CardDirection,
     pub easiness_factor: f64,
     pub interval_days: i32,
     pub repetitions: i32,
     pub next_review_date: SystemTime,
+    /// FSRS memory stability in days (0 until the card is first graded by FSRS)
+    pub stability: f64,
+    /// FSRS difficulty, 1-10 (0 until the card is first graded by FSRS)
+    pub difficulty: f64,
 }
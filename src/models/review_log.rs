@@ -0,0 +1,20 @@
+//! History entry recorded every time a card is graded.
+use super::CardDirection;
+use std::time::{Duration, SystemTime};
+
+#[derive(Clone, Debug)]
+pub struct ReviewLogEntry {
+    /// Row ID assigned by the database (ignored when inserting)
+    pub id: i64,
+    pub flashcard_id: i64,
+    pub direction: CardDirection,
+    /// Date of the review as returned by `get_current_date`
+    pub reviewed_at: SystemTime,
+    pub quality: u8,
+    pub previous_interval_days: i32,
+    pub new_interval_days: i32,
+    pub previous_easiness_factor: f64,
+    pub new_easiness_factor: f64,
+    /// Time between showing the card and grading it
+    pub time_taken: Duration,
+}
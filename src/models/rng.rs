@@ -0,0 +1,29 @@
+//! Seeded pseudo-random numbers for shuffling quiz choices and simulating
+//! reviews, so the same seed always gives the same result.
+
+/// Small deterministic generator (SplitMix64), good enough for shuffling
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// True with the given probability
+    pub(crate) fn chance(&mut self, probability: f64) -> bool {
+        // The top 53 bits give an evenly distributed f64 in [0, 1)
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    /// Fisher-Yates shuffle
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
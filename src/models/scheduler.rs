@@ -0,0 +1,90 @@
+//! Pluggable review schedulers.
+//!
+//! A `Scheduler` turns a quality grade (0-5) into updated review data. The
+//! scheduler used for a deck is stored in the database as a `SchedulerKind`.
+
+use super::ReviewData;
+use super::fsrs::FsrsScheduler;
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// Calculates the next review of a card after it has been graded.
+pub trait Scheduler {
+    /// quality: 0-5 (0 = complete blackout, 5 = perfect response)
+    fn next_review(
+        &self,
+        review_data: &ReviewData,
+        quality: u8,
+        current_date: SystemTime,
+    ) -> ReviewData;
+}
+
+/// The original SuperMemo SM-2 algorithm.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sm2Scheduler;
+
+impl Scheduler for Sm2Scheduler {
+    fn next_review(
+        &self,
+        review_data: &ReviewData,
+        quality: u8,
+        current_date: SystemTime,
+    ) -> ReviewData {
+        super::sm2::calculate_next_review(review_data, quality, current_date)
+    }
+}
+
+/// Scheduler selectable per deck
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SchedulerKind {
+    #[default]
+    Sm2,
+    Fsrs,
+}
+
+impl SchedulerKind {
+    pub const ALL: [SchedulerKind; 2] = [SchedulerKind::Sm2, SchedulerKind::Fsrs];
+
+    /// Identifier stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchedulerKind::Sm2 => "sm2",
+            SchedulerKind::Fsrs => "fsrs",
+        }
+    }
+
+    /// Human readable name for the UI
+    pub fn label(&self) -> &'static str {
+        match self {
+            SchedulerKind::Sm2 => "SM-2",
+            SchedulerKind::Fsrs => "FSRS",
+        }
+    }
+
+    /// Creates the scheduler with its default parameters
+    pub fn scheduler(&self) -> Box<dyn Scheduler> {
+        match self {
+            SchedulerKind::Sm2 => Box::new(Sm2Scheduler),
+            SchedulerKind::Fsrs => Box::new(FsrsScheduler::default()),
+        }
+    }
+}
+
+impl fmt::Display for SchedulerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for SchedulerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sm2" | "sm-2" => Ok(SchedulerKind::Sm2),
+            "fsrs" => Ok(SchedulerKind::Fsrs),
+            other => Err(format!("Unknown scheduler '{}'", other)),
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Forecast of the daily workload of a deck.
+//! Without a recall probability only the current due dates are counted. With
+//! one, every review is simulated with the deck's scheduler: a card is recalled
+//! with the given probability, and forgotten cards are repeated until recalled,
+//! like in a learning session. The deck's daily limits apply on every day, so reviews
+//! above the limit are postponed to the next day.
+
+use super::clock::{due_cutoff, learning_day};
+use super::limits::{DailyLimits, StudiedToday};
+use super::rng::Rng;
+use super::{CardDirection, ReviewData, Scheduler};
+use chrono::NaiveDate;
+use std::time::SystemTime;
+
+/// Grade of a simulated review that was recalled
+pub const RECALLED_QUALITY: u8 = 4;
+/// Grade of a simulated review that was forgotten
+pub const FORGOTTEN_QUALITY: u8 = 1;
+
+/// Lowest recall probability simulated, so forgotten cards are eventually recalled
+const MIN_RECALL_PROBABILITY: f64 = 0.01;
+
+/// Settings of a workload forecast
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationOptions {
+    /// Number of learning days projected, today first
+    pub days: usize,
+    /// Chance of recalling a card; None only counts the cards already scheduled
+    pub recall_probability: Option<f64>,
+    /// Cards to be added to the deck, introduced after its other new cards
+    pub added_cards: usize,
+    /// Seed of the generator deciding which reviews are recalled
+    pub seed: u64,
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        Self {
+            days: 30,
+            recall_probability: Some(0.9),
+            added_cards: 0,
+            seed: 0,
+        }
+    }
+}
+
+/// Cards studied on one day of the forecast
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DayWorkload {
+    pub date: NaiveDate,
+    /// Cards reviewed again
+    pub reviews: usize,
+    /// Cards studied for the first time
+    pub new_cards: usize,
+    /// Due cards postponed because of the daily review limit
+    pub backlog: usize,
+}
+
+/// Review data of a card before its first review
+fn new_card(now: SystemTime) -> ReviewData {
+    ReviewData {
+        flashcard_id: 0,
+        direction: CardDirection::Forward,
+        easiness_factor: 2.5,
+        interval_days: 0,
+        repetitions: 0,
+        next_review_date: now,
+        stability: 0.0,
+        difficulty: 0.0,
+    }
+}
+
+/// Grades a card until it is recalled
+fn simulate_review(
+    card: &mut ReviewData,
+    now: SystemTime,
+    probability: f64,
+    scheduler: &dyn Scheduler,
+    rng: &mut Rng,
+) {
+    loop {
+        let recalled = rng.chance(probability);
+        let quality = if recalled {
+            RECALLED_QUALITY
+        } else {
+            FORGOTTEN_QUALITY
+        };
+        *card = scheduler.next_review(card, quality, now);
+        if recalled {
+            break;
+        }
+    }
+}
+
+/// Projects the cards studied on each day, starting with the learning day of `now`
+///
+/// `reviewed` holds the review data of the cards reviewed before, and
+/// `new_cards` is the number of cards never reviewed. `studied` are the cards
+/// already studied today, which count towards today's limits. Reviews are
+/// graded with `scheduler`.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_workload(
+    reviewed: &[ReviewData],
+    new_cards: usize,
+    limits: &DailyLimits,
+    studied: &StudiedToday,
+    now: SystemTime,
+    rollover_hour: u32,
+    scheduler: &dyn Scheduler,
+    options: &SimulationOptions,
+) -> Vec<DayWorkload> {
+    let probability = options
+        .recall_probability
+        .map(|p| p.clamp(MIN_RECALL_PROBABILITY, 1.0));
+    let mut rng = Rng(options.seed);
+    let mut scheduled = reviewed.to_vec();
+    let mut new_left = new_cards + options.added_cards;
+    let mut days = Vec::with_capacity(options.days);
+    // Studied at `now` today and at the start of every following learning day,
+    // which need not be 24 hours apart around daylight saving changes
+    let mut time = now;
+
+    for day in 0..options.days {
+        let date = learning_day(time, rollover_hour);
+        let (new_limit, review_limit) = if day == 0 {
+            limits.remaining(studied)
+        } else {
+            limits.remaining(&StudiedToday::default())
+        };
+
+        scheduled.sort_by_key(|card| card.next_review_date);
+        let due = scheduled
+            .iter()
+            .take_while(|card| learning_day(card.next_review_date, rollover_hour) <= date)
+            .count();
+        let reviews = due.min(review_limit);
+        let introduced = new_left.min(new_limit);
+        new_left -= introduced;
+
+        let mut studied_cards: Vec<ReviewData> = scheduled.drain(..reviews).collect();
+        studied_cards.extend((0..introduced).map(|_| new_card(time)));
+        // Without simulated grades the next review dates are unknown
+        if let Some(probability) = probability {
+            for card in &mut studied_cards {
+                simulate_review(card, time, probability, scheduler, &mut rng);
+            }
+            scheduled.extend(studied_cards);
+        }
+
+        days.push(DayWorkload {
+            date,
+            reviews,
+            new_cards: introduced,
+            backlog: due - reviews,
+        });
+        time = due_cutoff(time, rollover_hour);
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::fsrs::FsrsScheduler;
+    use crate::models::scheduler::Sm2Scheduler;
+    use std::time::Duration;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn column(days: &[DayWorkload], value: fn(&DayWorkload) -> usize) -> Vec<usize> {
+        days.iter().map(value).collect()
+    }
+
+    #[test]
+    fn test_limits_postpone_reviews() {
+        let now = SystemTime::now();
+        let mut reviewed = vec![new_card(now - 3 * DAY), new_card(now), new_card(now + DAY)];
+        for card in &mut reviewed {
+            card.repetitions = 1;
+        }
+        let limits = DailyLimits {
+            new_cards: 2,
+            reviews: 1,
+        };
+        let options = SimulationOptions {
+            days: 3,
+            recall_probability: None,
+            ..SimulationOptions::default()
+        };
+
+        let days = simulate_workload(
+            &reviewed,
+            3,
+            &limits,
+            &StudiedToday::default(),
+            now,
+            0,
+            &Sm2Scheduler,
+            &options,
+        );
+
+        assert_eq!(column(&days, |d| d.reviews), vec![1, 1, 1]);
+        assert_eq!(column(&days, |d| d.backlog), vec![1, 1, 0]);
+        assert_eq!(column(&days, |d| d.new_cards), vec![2, 1, 0]);
+        assert!(
+            days.windows(2)
+                .all(|d| d[0].date.succ_opt() == Some(d[1].date))
+        );
+    }
+
+    #[test]
+    fn test_simulated_reviews_follow_sm2_intervals() {
+        let options = SimulationOptions {
+            days: 8,
+            recall_probability: Some(1.0),
+            added_cards: 1,
+            seed: 7,
+        };
+
+        let days = simulate_workload(
+            &[],
+            0,
+            &DailyLimits::default(),
+            &StudiedToday::default(),
+            SystemTime::now(),
+            0,
+            &Sm2Scheduler,
+            &options,
+        );
+
+        // Recalled cards are due again after 1 and then 6 days
+        assert_eq!(column(&days, |d| d.new_cards), vec![1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(column(&days, |d| d.reviews), vec![0, 1, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_simulated_reviews_use_the_given_scheduler() {
+        let options = SimulationOptions {
+            days: 5,
+            recall_probability: Some(1.0),
+            added_cards: 1,
+            seed: 7,
+        };
+
+        let days = simulate_workload(
+            &[],
+            0,
+            &DailyLimits::default(),
+            &StudiedToday::default(),
+            SystemTime::now(),
+            0,
+            &FsrsScheduler::default(),
+            &options,
+        );
+
+        // FSRS schedules a recalled new card after 4 days rather than 1
+        assert_eq!(column(&days, |d| d.reviews), vec![0, 0, 0, 0, 1]);
+    }
+}
@@ -47,26 +47,24 @@ pub fn calculate_next_review(
 
     ReviewData {
         flashcard_id: review_data.flashcard_id,
+        direction: review_data.direction,
         easiness_factor: new_ef,
         interval_days: new_interval,
         repetitions: new_repetitions,
         next_review_date: next_date,
+        stability: review_data.stability,
+        difficulty: review_data.difficulty,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::test_support::review_data;
 
     #[test]
     fn test_first_review() {
-        let review = ReviewData {
-            flashcard_id: 1,
-            easiness_factor: 2.5,
-            interval_days: 0,
-            repetitions: 0,
-            next_review_date: SystemTime::now(),
-        };
+        let review = review_data(1, SystemTime::now());
 
         let next = calculate_next_review(&review, 4, SystemTime::now());
         assert_eq!(next.interval_days, 1);
@@ -76,11 +74,9 @@ mod tests {
     #[test]
     fn test_second_review() {
         let review = ReviewData {
-            flashcard_id: 1,
-            easiness_factor: 2.5,
             interval_days: 1,
             repetitions: 1,
-            next_review_date: SystemTime::now(),
+            ..review_data(1, SystemTime::now())
         };
 
         let next = calculate_next_review(&review, 4, SystemTime::now());
@@ -91,11 +87,9 @@ mod tests {
     #[test]
     fn test_quality_below_3_resets() {
         let review = ReviewData {
-            flashcard_id: 1,
-            easiness_factor: 2.5,
             interval_days: 10,
             repetitions: 5,
-            next_review_date: SystemTime::now(),
+            ..review_data(1, SystemTime::now())
         };
 
         let next = calculate_next_review(&review, 2, SystemTime::now());
@@ -108,11 +102,10 @@ mod tests {
     #[test]
     fn test_ef_floor() {
         let review = ReviewData {
-            flashcard_id: 1,
             easiness_factor: 1.3,
             interval_days: 1,
             repetitions: 1,
-            next_review_date: SystemTime::now(),
+            ..review_data(1, SystemTime::now())
         };
 
         let next = calculate_next_review(&review, 0, SystemTime::now());
@@ -0,0 +1,274 @@
+//! Study statistics computed from review data and the review history.
+//! Cards are counted per direction, like everywhere else in scheduling: a card
+//! studied both ways counts twice. Days are learning days, see `clock`.
+
+use super::clock::learning_day;
+use super::{CardDirection, ReviewData, ReviewLogEntry};
+use chrono::{Duration, NaiveDate};
+use std::collections::{BTreeMap, HashSet};
+use std::time::SystemTime;
+
+/// Interval from which a card counts as mature
+pub const MATURE_INTERVAL_DAYS: i32 = 21;
+/// Number of days covered by the review history and the forecast
+pub const STATISTICS_DAYS: usize = 30;
+
+/// Lowest easiness factor of SM-2, the start of the first bucket
+const MIN_EASINESS: f64 = 1.3;
+const EASINESS_BUCKET_WIDTH: f64 = 0.2;
+const EASINESS_BUCKETS: usize = 9;
+
+/// Intervals before a review grouped for the retention rate, with their labels
+const INTERVAL_BUCKETS: [(i32, &str); 5] = [
+    (0, "new/relearning"),
+    (1, "1-6 days"),
+    (7, "7-20 days"),
+    (21, "21-89 days"),
+    (90, "90+ days"),
+];
+
+/// Cards of a deck by how well they are known
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeckStatistics {
+    pub deck_name: String,
+    /// Never reviewed
+    pub new_cards: usize,
+    /// Reviewed, with an interval shorter than `MATURE_INTERVAL_DAYS`
+    pub learning: usize,
+    pub mature: usize,
+}
+
+/// Reviews of cards whose interval was in a range, and how many were recalled
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetentionBucket {
+    pub label: &'static str,
+    pub reviews: usize,
+    /// Reviews graded 3 or better
+    pub passed: usize,
+}
+
+impl RetentionBucket {
+    /// Share of passed reviews, or None without reviews
+    pub fn rate(&self) -> Option<f64> {
+        (self.reviews > 0).then(|| self.passed as f64 / self.reviews as f64)
+    }
+}
+
+/// Everything shown on the statistics screen
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Statistics {
+    /// One entry per deck, sorted by name
+    pub decks: Vec<DeckStatistics>,
+    /// Reviews on each of the last `STATISTICS_DAYS` days, today last
+    pub reviews_per_day: Vec<(NaiveDate, usize)>,
+    pub retention: Vec<RetentionBucket>,
+    /// Reviewed cards per easiness factor range, keyed by the lower bound;
+    /// the last bucket has no upper bound
+    pub easiness: Vec<(f64, usize)>,
+    pub average_easiness: Option<f64>,
+    /// Cards due on each of the next `STATISTICS_DAYS` days, today first
+    /// (including overdue cards); new cards are not included
+    pub forecast: Vec<usize>,
+}
+
+impl Statistics {
+    /// Computes the statistics of `cards`, given with the name of their deck
+    ///
+    /// `decks` lists every deck, so decks without cards are shown too. `log`
+    /// is the review history of the same cards.
+    pub fn compute(
+        decks: &[String],
+        cards: &[(String, ReviewData)],
+        log: &[ReviewLogEntry],
+        now: SystemTime,
+        rollover_hour: u32,
+    ) -> Self {
+        let today = learning_day(now, rollover_hour);
+        let reviewed: HashSet<(i64, CardDirection)> = log
+            .iter()
+            .map(|entry| (entry.flashcard_id, entry.direction))
+            .collect();
+
+        let mut per_deck: BTreeMap<&str, DeckStatistics> = decks
+            .iter()
+            .map(|name| {
+                (
+                    name.as_str(),
+                    DeckStatistics {
+                        deck_name: name.clone(),
+                        ..DeckStatistics::default()
+                    },
+                )
+            })
+            .collect();
+        let mut easiness = vec![0; EASINESS_BUCKETS];
+        let mut easiness_sum = 0.0;
+        let mut forecast = vec![0; STATISTICS_DAYS];
+
+        for (deck_name, review) in cards {
+            let deck = per_deck
+                .entry(deck_name.as_str())
+                .or_insert_with(|| DeckStatistics {
+                    deck_name: deck_name.clone(),
+                    ..DeckStatistics::default()
+                });
+            if !reviewed.contains(&(review.flashcard_id, review.direction)) {
+                deck.new_cards += 1;
+                continue;
+            }
+            if review.interval_days >= MATURE_INTERVAL_DAYS {
+                deck.mature += 1;
+            } else {
+                deck.learning += 1;
+            }
+
+            // A small tolerance keeps bounds such as 2.5 in their own bucket
+            let bucket = ((review.easiness_factor - MIN_EASINESS) / EASINESS_BUCKET_WIDTH + 1e-9)
+                .floor()
+                .clamp(0.0, (EASINESS_BUCKETS - 1) as f64) as usize;
+            easiness[bucket] += 1;
+            easiness_sum += review.easiness_factor;
+
+            let day = (learning_day(review.next_review_date, rollover_hour) - today)
+                .num_days()
+                .max(0) as usize;
+            if day < STATISTICS_DAYS {
+                forecast[day] += 1;
+            }
+        }
+
+        let reviewed_cards: usize = easiness.iter().sum();
+        let average_easiness = (reviewed_cards > 0).then(|| easiness_sum / reviewed_cards as f64);
+
+        Statistics {
+            decks: per_deck.into_values().collect(),
+            reviews_per_day: reviews_per_day(log, today, rollover_hour),
+            retention: retention(log),
+            easiness: easiness
+                .into_iter()
+                .enumerate()
+                .map(|(i, count)| (MIN_EASINESS + i as f64 * EASINESS_BUCKET_WIDTH, count))
+                .collect(),
+            average_easiness,
+            forecast,
+        }
+    }
+}
+
+/// Counts the reviews of the last `STATISTICS_DAYS` days up to `today`
+fn reviews_per_day(
+    log: &[ReviewLogEntry],
+    today: NaiveDate,
+    rollover_hour: u32,
+) -> Vec<(NaiveDate, usize)> {
+    let mut days: Vec<(NaiveDate, usize)> = (0..STATISTICS_DAYS)
+        .rev()
+        .map(|ago| (today - Duration::days(ago as i64), 0))
+        .collect();
+    for entry in log {
+        let ago = (today - learning_day(entry.reviewed_at, rollover_hour)).num_days();
+        if (0..STATISTICS_DAYS as i64).contains(&ago) {
+            days[STATISTICS_DAYS - 1 - ago as usize].1 += 1;
+        }
+    }
+    days
+}
+
+/// Groups the reviews by the interval the card had before being graded
+fn retention(log: &[ReviewLogEntry]) -> Vec<RetentionBucket> {
+    let mut buckets: Vec<RetentionBucket> = INTERVAL_BUCKETS
+        .iter()
+        .map(|&(_, label)| RetentionBucket {
+            label,
+            reviews: 0,
+            passed: 0,
+        })
+        .collect();
+    for entry in log {
+        let index = INTERVAL_BUCKETS
+            .iter()
+            .rposition(|&(min, _)| entry.previous_interval_days >= min)
+            .unwrap_or(0);
+        buckets[index].reviews += 1;
+        if entry.quality >= 3 {
+            buckets[index].passed += 1;
+        }
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_support::{review_data, review_log_entry};
+    use chrono::{Local, TimeZone};
+
+    fn day(day: u32) -> SystemTime {
+        Local
+            .with_ymd_and_hms(2024, 3, day, 12, 0, 0)
+            .unwrap()
+            .into()
+    }
+
+    fn card(id: i64, interval_days: i32, easiness_factor: f64, due: u32) -> ReviewData {
+        ReviewData {
+            easiness_factor,
+            interval_days,
+            repetitions: 1,
+            ..review_data(id, day(due))
+        }
+    }
+
+    fn review(id: i64, reviewed: u32, previous_interval_days: i32, quality: u8) -> ReviewLogEntry {
+        let card = review_data(id, day(reviewed));
+        ReviewLogEntry {
+            previous_interval_days,
+            ..review_log_entry(&card, &card, quality, day(reviewed))
+        }
+    }
+
+    #[test]
+    fn test_compute() {
+        let decks = vec!["Empty".to_string(), "Polish".to_string()];
+        let cards = vec![
+            ("Polish".to_string(), card(1, 0, 2.5, 10)),
+            ("Polish".to_string(), card(2, 6, 2.5, 8)),
+            ("Polish".to_string(), card(3, 30, 1.3, 20)),
+        ];
+        let log = vec![
+            review(2, 9, 0, 4),
+            review(2, 10, 1, 2),
+            review(3, 10, 25, 5),
+        ];
+
+        let stats = Statistics::compute(&decks, &cards, &log, day(10), 0);
+
+        assert_eq!(stats.decks[0].deck_name, "Empty");
+        assert_eq!(
+            stats.decks[1],
+            DeckStatistics {
+                deck_name: "Polish".to_string(),
+                new_cards: 1,
+                learning: 1,
+                mature: 1,
+            }
+        );
+        assert_eq!(
+            stats.reviews_per_day.last(),
+            Some(&(learning_day(day(10), 0), 2))
+        );
+        assert_eq!(stats.reviews_per_day[STATISTICS_DAYS - 2].1, 1);
+
+        let rates: Vec<Option<f64>> = stats.retention.iter().map(RetentionBucket::rate).collect();
+        assert_eq!(rates, vec![Some(1.0), Some(0.0), None, Some(1.0), None]);
+
+        assert_eq!(stats.easiness[0].1, 1);
+        assert_eq!(stats.easiness[6].1, 1);
+        assert!((stats.average_easiness.unwrap() - 1.9).abs() < 1e-9);
+
+        // The overdue card is due today, the new card is left out
+        assert_eq!(stats.forecast[0], 1);
+        assert_eq!(stats.forecast[10], 1);
+        assert_eq!(stats.forecast.iter().sum::<usize>(), 2);
+    }
+}
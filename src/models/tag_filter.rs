@@ -0,0 +1,302 @@
+//! Tag expressions selecting flashcards, e.g. `lesson1 and (noun or verb) and not irregular`.
+//! Tags written next to each other must all match, `*` at the end of a tag
+//! matches any suffix, and tags are compared ignoring case. `&`, `|` and `!`
+//! can be used instead of `and`, `or` and `not`.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Parsed tag expression
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TagFilter {
+    /// Matches every card (the empty expression)
+    #[default]
+    Any,
+    Tag(String),
+    Not(Box<TagFilter>),
+    And(Vec<TagFilter>),
+    Or(Vec<TagFilter>),
+}
+
+impl TagFilter {
+    /// True if a card with these tags is selected
+    pub fn matches(&self, tags: &[String]) -> bool {
+        match self {
+            TagFilter::Any => true,
+            TagFilter::Tag(pattern) => tags.iter().any(|tag| tag_matches(pattern, tag)),
+            TagFilter::Not(inner) => !inner.matches(tags),
+            TagFilter::And(all) => all.iter().all(|f| f.matches(tags)),
+            TagFilter::Or(any) => any.iter().any(|f| f.matches(tags)),
+        }
+    }
+}
+
+fn tag_matches(pattern: &str, tag: &str) -> bool {
+    let tag = tag.to_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tag.starts_with(prefix),
+        None => tag == pattern,
+    }
+}
+
+/// Returns an error message if `tag` cannot be used as a tag
+///
+/// Tags are single words, so they cannot contain whitespace, parentheses or
+/// the operator characters, and cannot be an operator word.
+pub fn validate_tag(tag: &str) -> Result<(), String> {
+    if tag.is_empty() {
+        return Err("Tags cannot be empty".to_string());
+    }
+    if tag
+        .chars()
+        .any(|c| c.is_whitespace() || "()&|!*".contains(c))
+    {
+        return Err(format!(
+            "Tag '{}' cannot contain spaces or any of ( ) & | ! *",
+            tag
+        ));
+    }
+    if ["and", "or", "not"].contains(&tag.to_lowercase().as_str()) {
+        return Err(format!("'{}' cannot be used as a tag", tag));
+    }
+    Ok(())
+}
+
+/// Splits tags typed by the user, separated by whitespace or commas
+pub fn split_tags(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Tag(String),
+}
+
+fn tokenize(expression: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+
+    let flush = |word: &mut String, tokens: &mut Vec<Token>| {
+        if word.is_empty() {
+            return;
+        }
+        tokens.push(match word.to_lowercase().as_str() {
+            "and" => Token::And,
+            "or" => Token::Or,
+            "not" => Token::Not,
+            other => Token::Tag(other.to_string()),
+        });
+        word.clear();
+    };
+
+    for c in expression.chars() {
+        let token = match c {
+            '(' => Some(Token::Open),
+            ')' => Some(Token::Close),
+            '&' => Some(Token::And),
+            '|' => Some(Token::Or),
+            '!' => Some(Token::Not),
+            c if c.is_whitespace() => None,
+            c => {
+                word.push(c);
+                continue;
+            }
+        };
+        flush(&mut word, &mut tokens);
+        tokens.extend(token);
+    }
+    flush(&mut word, &mut tokens);
+    tokens
+}
+
+/// Recursive descent parser; `or` binds weaker than `and`, which binds weaker than `not`
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<TagFilter, String> {
+        let mut terms = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            TagFilter::Or(terms)
+        })
+    }
+
+    fn and(&mut self) -> Result<TagFilter, String> {
+        let mut factors = vec![self.not()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                // Juxtaposition means "and"
+                Some(Token::Not | Token::Open | Token::Tag(_)) => {}
+                _ => break,
+            }
+            factors.push(self.not()?);
+        }
+        Ok(if factors.len() == 1 {
+            factors.remove(0)
+        } else {
+            TagFilter::And(factors)
+        })
+    }
+
+    fn not(&mut self) -> Result<TagFilter, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(TagFilter::Not(Box::new(self.not()?))),
+            Some(Token::Open) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err("Missing ')' in tag expression".to_string()),
+                }
+            }
+            Some(Token::Tag(tag)) => Ok(TagFilter::Tag(tag)),
+            Some(token) => Err(format!("Unexpected {} in tag expression", describe(&token))),
+            None => Err("Tag expression ends unexpectedly".to_string()),
+        }
+    }
+}
+
+fn describe(token: &Token) -> &'static str {
+    match token {
+        Token::And => "'and'",
+        Token::Or => "'or'",
+        Token::Not => "'not'",
+        Token::Open => "'('",
+        Token::Close => "')'",
+        Token::Tag(_) => "tag",
+    }
+}
+
+impl FromStr for TagFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s);
+        if tokens.is_empty() {
+            return Ok(TagFilter::Any);
+        }
+
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let filter = parser.or()?;
+        match parser.peek() {
+            None => Ok(filter),
+            Some(token) => Err(format!("Unexpected {} in tag expression", describe(token))),
+        }
+    }
+}
+
+impl fmt::Display for TagFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, parts: &[TagFilter], op: &str| {
+            write!(f, "(")?;
+            for (i, part) in parts.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                write!(f, "{}", part)?;
+            }
+            write!(f, ")")
+        };
+        match self {
+            TagFilter::Any => Ok(()),
+            TagFilter::Tag(tag) => f.write_str(tag),
+            TagFilter::Not(inner) => write!(f, "not {}", inner),
+            TagFilter::And(all) => join(f, all, "and"),
+            TagFilter::Or(any) => join(f, any, "or"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(list: &str) -> Vec<String> {
+        list.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn matches(expression: &str, card_tags: &str) -> bool {
+        expression
+            .parse::<TagFilter>()
+            .unwrap()
+            .matches(&tags(card_tags))
+    }
+
+    #[test]
+    fn test_operators() {
+        assert!(matches("", "anything"));
+        assert!(matches("noun", "Lesson1 Noun"));
+        assert!(matches("lesson1 noun", "lesson1 noun"));
+        assert!(!matches("lesson1 noun", "lesson1 verb"));
+        assert!(matches("noun or verb", "verb"));
+        assert!(matches(
+            "lesson1 and (noun | verb) and not irregular",
+            "lesson1 verb"
+        ));
+        assert!(!matches(
+            "lesson1 & (noun | verb) & !irregular",
+            "lesson1 verb irregular"
+        ));
+        assert!(matches("lesson*", "lesson12"));
+    }
+
+    #[test]
+    fn test_precedence() {
+        // "and" binds tighter than "or"
+        assert!(matches("a or b and c", "a"));
+        assert!(!matches("(a or b) and c", "a"));
+        assert_eq!(
+            "a or b c".parse::<TagFilter>().unwrap().to_string(),
+            "(a or (b and c))"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("(noun".parse::<TagFilter>().is_err());
+        assert!("noun or".parse::<TagFilter>().is_err());
+        assert!("noun )".parse::<TagFilter>().is_err());
+    }
+
+    #[test]
+    fn test_validate_tag() {
+        assert!(validate_tag("lesson-1").is_ok());
+        assert!(validate_tag("two words").is_err());
+        assert!(validate_tag("Not").is_err());
+        assert_eq!(
+            split_tags(" noun, lesson1  verb,"),
+            vec!["noun", "lesson1", "verb"]
+        );
+    }
+}
@@ -0,0 +1,38 @@
+//! Review data and history fixtures shared by the tests of several modules.
+use super::{CardDirection, ReviewData, ReviewLogEntry};
+use std::time::{Duration, SystemTime};
+
+/// Review data of a forward card that was never reviewed, due at `due`
+pub fn review_data(flashcard_id: i64, due: SystemTime) -> ReviewData {
+    ReviewData {
+        flashcard_id,
+        direction: CardDirection::Forward,
+        easiness_factor: 2.5,
+        interval_days: 0,
+        repetitions: 0,
+        next_review_date: due,
+        stability: 0.0,
+        difficulty: 0.0,
+    }
+}
+
+/// History entry of grading `before` with `quality`, which resulted in `after`
+pub fn review_log_entry(
+    before: &ReviewData,
+    after: &ReviewData,
+    quality: u8,
+    reviewed_at: SystemTime,
+) -> ReviewLogEntry {
+    ReviewLogEntry {
+        id: 0,
+        flashcard_id: before.flashcard_id,
+        direction: before.direction,
+        reviewed_at,
+        quality,
+        previous_interval_days: before.interval_days,
+        new_interval_days: after.interval_days,
+        previous_easiness_factor: before.easiness_factor,
+        new_easiness_factor: after.easiness_factor,
+        time_taken: Duration::ZERO,
+    }
+}